@@ -0,0 +1,85 @@
+//! Compares `Many` and `ManyIndexed` on a target with a large number of sources.
+//! Run with `cargo bench --bench fan_out`.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use librelations::{
+    cyclicity::Cyclic,
+    despawn_policy::Detach,
+    restriction::{Many, ManyIndexed, One},
+    symmetry::Directed,
+    EntityMutExt, EntityRefExt, RelKind,
+};
+
+const SOURCES: usize = 10_000;
+
+struct Linear;
+impl RelKind for Linear {
+    type Target = Entity;
+    type SourceRestriction = One;
+    type TargetRestriction = Many;
+    type Cyclicity = Cyclic;
+    type DespawnPolicy = Detach;
+    type TargetDespawnPolicy = Detach;
+    type Symmetry = Directed;
+}
+
+struct Indexed;
+impl RelKind for Indexed {
+    type Target = Entity;
+    type SourceRestriction = One;
+    type TargetRestriction = ManyIndexed;
+    type Cyclicity = Cyclic;
+    type DespawnPolicy = Detach;
+    type TargetDespawnPolicy = Detach;
+    type Symmetry = Directed;
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn bench<R: RelKind<Target = Entity>>(label: &str, make: fn() -> R) {
+    let mut world = World::new();
+    let target = world.spawn_empty().id();
+    let sources = (0..SOURCES)
+        .map(|_| world.spawn_empty().id())
+        .collect::<Vec<_>>();
+
+    let insert = time(|| {
+        for &source in &sources {
+            world.entity_mut(source).insert_relation(make(), target);
+        }
+    });
+    let lookup = time(|| {
+        for &source in &sources {
+            assert!(world.entity(target).get_noitaler::<R>(source).is_some());
+        }
+    });
+    let despawn_sources = time(|| {
+        for &source in &sources[..SOURCES / 2] {
+            world.despawn(source);
+        }
+    });
+    let despawn_target = time(|| {
+        world.despawn(target);
+    });
+
+    println!(
+        "{:<12} insert: {:>10.2?}  lookup: {:>10.2?}  despawn sources: {:>10.2?}  despawn target: {:>10.2?}",
+        label,
+        insert,
+        lookup,
+        despawn_sources,
+        despawn_target,
+    );
+}
+
+fn main() {
+    println!("{} sources into one target", SOURCES);
+    bench("Many", || Linear);
+    bench("ManyIndexed", || Indexed);
+}
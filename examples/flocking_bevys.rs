@@ -3,8 +3,10 @@ use std::ops::Add;
 use bevy::prelude::*;
 use librelations::{
     cyclicity::{Acyclic, Cyclic},
+    despawn_policy::Detach,
     restriction::{Many, One},
-    EntityCommandsExt, NoitalerRef, RelKind, RelationRef, WithRelation,
+    symmetry::Directed,
+    AggregateOp, AggregatePlugin, EntityCommandsExt, RelKind, RelationRef, Relations, WithRelation,
 };
 
 use rand::Rng;
@@ -17,22 +19,30 @@ fn main() {
         .add_system(move_bevys)
         .add_system(insert_group_target)
         .add_system(remove_group_target)
-        .add_system(set_group_position)
+        .add_plugin(AggregatePlugin::<InGroup, Transform, Group, MeanPosition>::default())
         .run();
 }
 
 struct InGroup;
 impl RelKind for InGroup {
+    type Target = Entity;
     type SourceRestriction = One;
     type TargetRestriction = Many;
     type Cyclicity = Acyclic;
+    type DespawnPolicy = Detach;
+    type TargetDespawnPolicy = Detach;
+    type Symmetry = Directed;
 }
 
 struct MoveToGroup;
 impl RelKind for MoveToGroup {
+    type Target = Entity;
     type SourceRestriction = One;
     type TargetRestriction = Many;
     type Cyclicity = Cyclic;
+    type DespawnPolicy = Detach;
+    type TargetDespawnPolicy = Detach;
+    type Symmetry = Directed;
 }
 
 #[derive(Component)]
@@ -113,17 +123,19 @@ fn move_camera(keys: Res<Input<KeyCode>>, mut query: Query<&mut Transform, With<
     transform.translation.y = transform.translation.y.clamp(-MAP_BOUNDS.1, MAP_BOUNDS.1);
 }
 
-fn set_group_position(
-    bevys: Query<&Transform>,
-    mut groups: Query<(&mut Group, NoitalerRef<InGroup>)>,
-) {
-    for (mut group, sources) in &mut groups {
-        let average_pos = sources
-            .iter()
-            .map(|source| bevys.get(source).unwrap().translation.truncate())
-            .sum::<Vec2>()
-            / sources.iter().count() as f32; // really this ought to be `Itertools::tree_fold_1(|a, b| (a + b) / 2.0)`
-        group.position = average_pos;
+struct MeanPosition;
+impl AggregateOp<Transform, Group> for MeanPosition {
+    type Acc = (Vec2, f32);
+    fn init() -> (Vec2, f32) {
+        (Vec2::ZERO, 0.0)
+    }
+    fn fold((sum, count): (Vec2, f32), transform: &Transform) -> (Vec2, f32) {
+        (sum + transform.translation.truncate(), count + 1.0)
+    }
+    fn finish((sum, count): (Vec2, f32), group: &mut Group) {
+        if count != 0.0 {
+            group.position = sum / count;
+        }
     }
 }
 
@@ -166,16 +178,15 @@ fn insert_group_target(
 
 fn move_bevys(
     mut bevys: Query<(&mut Transform, &TargetOffset, RelationRef<InGroup>)>,
-    groups: Query<(&Group, Option<RelationRef<MoveToGroup>>)>,
+    groups: Query<&Group>,
+    move_to: Relations<MoveToGroup>,
 ) {
     for (mut bevy_pos, offset, in_group) in &mut bevys {
         let in_group = in_group.into_iter().next().unwrap().0;
-        let move_to_group = match groups.get(in_group).unwrap() {
-            (_, None) => continue,
-            (_, Some(move_to_group)) => move_to_group,
+        let move_to_group = match move_to.targets(in_group).next() {
+            None => continue,
+            Some(move_to_group) => groups.get(move_to_group).unwrap(),
         };
-        let move_to_group = move_to_group.into_iter().next().unwrap().0;
-        let (move_to_group, _) = groups.get(move_to_group).unwrap();
 
         let velocity = ((move_to_group.position + offset.0) - bevy_pos.translation.truncate())
             .normalize_or_zero();
@@ -0,0 +1,208 @@
+use std::{
+    marker::PhantomData,
+    ops::{Add, Div},
+};
+
+use bevy::{
+    app::{App, CoreStage, Plugin},
+    ecs::prelude::*,
+    utils::HashSet,
+};
+
+use crate::{ChangedNoitaler, Noitaler, NoitalerRef, RelKind, RelationRef};
+
+/// The value of a source component that the built in [`AggregateOp`]s work with
+pub trait AggregateSource: Component {
+    type Value;
+    fn value(&self) -> Self::Value;
+}
+
+/// A target component that the built in [`AggregateOp`]s can write their result into
+pub trait AggregateTarget<V>: Component {
+    fn set(&mut self, value: V);
+}
+
+/// Folds `Src` from every source of a target into `Dst` on the target, see [`Sum`], [`Mean`],
+/// [`Min`], [`Max`] and [`Count`]. Sources without `Src` are skipped.
+pub trait AggregateOp<Src, Dst>: Send + Sync + 'static {
+    type Acc;
+    fn init() -> Self::Acc;
+    fn fold(acc: Self::Acc, src: &Src) -> Self::Acc;
+    /// Writes the result into `dst`, `acc` is [`AggregateOp::init`] if there are no sources left
+    fn finish(acc: Self::Acc, dst: &mut Dst);
+}
+
+/// Sum of the source values, targets without sources are set to the default value
+pub struct Sum;
+/// Mean of the source values, targets without sources are left unchanged
+pub struct Mean;
+/// Smallest source value, targets without sources are left unchanged
+pub struct Min;
+/// Largest source value, targets without sources are left unchanged
+pub struct Max;
+/// Number of sources with `Src`
+pub struct Count;
+
+impl<Src, Dst> AggregateOp<Src, Dst> for Sum
+where
+    Src: AggregateSource,
+    Src::Value: Default + Add<Output = Src::Value>,
+    Dst: AggregateTarget<Src::Value>,
+{
+    type Acc = Src::Value;
+    fn init() -> Src::Value {
+        Default::default()
+    }
+    fn fold(acc: Src::Value, src: &Src) -> Src::Value {
+        acc + src.value()
+    }
+    fn finish(acc: Src::Value, dst: &mut Dst) {
+        dst.set(acc);
+    }
+}
+
+impl<Src, Dst> AggregateOp<Src, Dst> for Mean
+where
+    Src: AggregateSource,
+    Src::Value: Default + Add<Output = Src::Value> + Div<f32, Output = Src::Value>,
+    Dst: AggregateTarget<Src::Value>,
+{
+    type Acc = (Src::Value, usize);
+    fn init() -> (Src::Value, usize) {
+        (Default::default(), 0)
+    }
+    fn fold((sum, count): (Src::Value, usize), src: &Src) -> (Src::Value, usize) {
+        (sum + src.value(), count + 1)
+    }
+    fn finish((sum, count): (Src::Value, usize), dst: &mut Dst) {
+        if count != 0 {
+            dst.set(sum / count as f32);
+        }
+    }
+}
+
+impl<Src, Dst> AggregateOp<Src, Dst> for Min
+where
+    Src: AggregateSource,
+    Src::Value: PartialOrd,
+    Dst: AggregateTarget<Src::Value>,
+{
+    type Acc = Option<Src::Value>;
+    fn init() -> Option<Src::Value> {
+        None
+    }
+    fn fold(acc: Option<Src::Value>, src: &Src) -> Option<Src::Value> {
+        let value = src.value();
+        match acc {
+            Some(acc) if acc <= value => Some(acc),
+            _ => Some(value),
+        }
+    }
+    fn finish(acc: Option<Src::Value>, dst: &mut Dst) {
+        if let Some(acc) = acc {
+            dst.set(acc);
+        }
+    }
+}
+
+impl<Src, Dst> AggregateOp<Src, Dst> for Max
+where
+    Src: AggregateSource,
+    Src::Value: PartialOrd,
+    Dst: AggregateTarget<Src::Value>,
+{
+    type Acc = Option<Src::Value>;
+    fn init() -> Option<Src::Value> {
+        None
+    }
+    fn fold(acc: Option<Src::Value>, src: &Src) -> Option<Src::Value> {
+        let value = src.value();
+        match acc {
+            Some(acc) if acc >= value => Some(acc),
+            _ => Some(value),
+        }
+    }
+    fn finish(acc: Option<Src::Value>, dst: &mut Dst) {
+        if let Some(acc) = acc {
+            dst.set(acc);
+        }
+    }
+}
+
+impl<Src, Dst> AggregateOp<Src, Dst> for Count
+where
+    Src: Component,
+    Dst: AggregateTarget<usize>,
+{
+    type Acc = usize;
+    fn init() -> usize {
+        0
+    }
+    fn fold(acc: usize, _: &Src) -> usize {
+        acc + 1
+    }
+    fn finish(acc: usize, dst: &mut Dst) {
+        dst.set(acc);
+    }
+}
+
+/// Folds `Src` from the sources of every target into `Dst` on the target using `Op` in
+/// [`CoreStage::PostUpdate`]. Targets are only recomputed if a source was added or removed or had
+/// its `Src` changed. `Src` and `Dst` must be different components.
+pub struct AggregatePlugin<R, Src, Dst, Op>(PhantomData<fn(R, Src, Dst) -> Op>);
+impl<R, Src, Dst, Op> Default for AggregatePlugin<R, Src, Dst, Op> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<R, Src, Dst, Op> Plugin for AggregatePlugin<R, Src, Dst, Op>
+where
+    R: RelKind<Target = Entity>,
+    Src: Component,
+    Dst: Component,
+    Op: AggregateOp<Src, Dst>,
+{
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PostUpdate, aggregate_system::<R, Src, Dst, Op>);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn aggregate_system<R, Src, Dst, Op>(
+    changed_targets: Query<Entity, ChangedNoitaler<R>>,
+    changed_sources: Query<RelationRef<R>, Changed<Src>>,
+    relations: Query<RelationRef<R>>,
+    noitalers: Query<NoitalerRef<R>>,
+    srcs: Query<&Src>,
+    mut dsts: Query<&mut Dst>,
+    removed_srcs: RemovedComponents<Src>,
+    detached_targets: RemovedComponents<Noitaler<R>>,
+) where
+    R: RelKind<Target = Entity>,
+    Src: Component,
+    Dst: Component,
+    Op: AggregateOp<Src, Dst>,
+{
+    let mut dirty = changed_targets.iter().collect::<HashSet<_>>();
+    dirty.extend(detached_targets.iter());
+    dirty.extend(changed_sources.iter().flatten().map(|(target, _)| target));
+    dirty.extend(
+        removed_srcs
+            .iter()
+            .filter_map(|source| relations.get(source).ok())
+            .flatten()
+            .map(|(target, _)| target),
+    );
+
+    for target in dirty {
+        let mut dst = match dsts.get_mut(target) {
+            Ok(dst) => dst,
+            Err(_) => continue,
+        };
+        let acc = srcs
+            .iter_many(noitalers.get(target).ok().into_iter().flatten())
+            .fold(Op::init(), Op::fold);
+        Op::finish(acc, &mut dst);
+    }
+}
@@ -0,0 +1,73 @@
+use std::any::TypeId;
+
+use bevy::{
+    app::{App, AppTypeRegistry, CoreStage},
+    ecs::{prelude::*, schedule::IntoSystemDescriptor},
+    reflect::{FromReflect, FromType, GetTypeRegistration},
+};
+
+use crate::{
+    scene::{load_scene_relations_system, ReflectSceneRelation},
+    ReflectRelation, RelKind, RelationEvent, SceneRelation,
+};
+
+pub trait RelationAppExt {
+    /// Starts sending [`RelationEvent<T>`] which is also what
+    /// [`RemovedRelations<T>`](crate::RemovedRelations) reads from
+    fn add_relation_events<T: RelKind>(&mut self) -> &mut Self;
+    /// Registers `T` in the [`AppTypeRegistry`] along with its [`ReflectRelation`]
+    fn register_relation<T>(&mut self) -> &mut Self
+    where
+        T: RelKind + FromReflect + GetTypeRegistration,
+        T::Target: FromReflect;
+    /// Like [`RelationAppExt::register_relation`] but also registers [`SceneRelation<T>`] so
+    /// that relations of kind `T` can be saved in scenes, see
+    /// [`dynamic_scene_with_relations`](crate::dynamic_scene_with_relations). Relations of spawned
+    /// scenes are loaded at the start of [`CoreStage::Update`].
+    fn register_scene_relation<T>(&mut self) -> &mut Self
+    where
+        T: RelKind<Target = Entity> + FromReflect + GetTypeRegistration;
+}
+
+impl RelationAppExt for App {
+    fn add_relation_events<T: RelKind>(&mut self) -> &mut Self {
+        self.add_event::<RelationEvent<T>>()
+    }
+
+    fn register_relation<T>(&mut self) -> &mut Self
+    where
+        T: RelKind + FromReflect + GetTypeRegistration,
+        T::Target: FromReflect,
+    {
+        self.register_type::<T>();
+        let registry = self.world.resource::<AppTypeRegistry>();
+        registry
+            .write()
+            .get_mut(TypeId::of::<T>())
+            .unwrap()
+            .insert(<ReflectRelation as FromType<T>>::from_type());
+        self
+    }
+
+    fn register_scene_relation<T>(&mut self) -> &mut Self
+    where
+        T: RelKind<Target = Entity> + FromReflect + GetTypeRegistration,
+    {
+        self.register_relation::<T>()
+            .register_type::<Entity>()
+            .register_type::<Vec<Entity>>()
+            .register_type::<Vec<T>>()
+            .register_type::<SceneRelation<T>>()
+            .add_system_to_stage(
+                CoreStage::Update,
+                load_scene_relations_system::<T>.at_start(),
+            );
+        let registry = self.world.resource::<AppTypeRegistry>();
+        registry
+            .write()
+            .get_mut(TypeId::of::<T>())
+            .unwrap()
+            .insert(<ReflectSceneRelation as FromType<T>>::from_type());
+        self
+    }
+}
@@ -0,0 +1,392 @@
+use std::fmt::Debug;
+
+use bevy::ecs::{prelude::*, system::SystemState};
+
+use crate::{
+    cyclicity::AssertTreeIfAcyclic, despawn_policy::Detach, symmetry::Directed, Applied,
+    EntityMutExt, EntityRefExt, Noitaler, NoitalerRef, RelKind, Relation, RelationError,
+    RelationRef, RelationsMut, Restriction,
+};
+
+/// Panics if the edges of kind `R` in `world` are inconsistent: every edge has to be stored on
+/// both its source and its target, and kinds that are
+/// [`cyclicity::Acyclic`](crate::cyclicity::Acyclic) may not contain cycles. Empty storages left
+/// behind by [`RelationsMut`] are allowed.
+pub fn assert_relation_graph_good<R: RelKind<Target = Entity>>(world: &mut World) {
+    let mut state = SystemState::<(
+        Query<(Entity, RelationRef<R>)>,
+        Query<(Entity, NoitalerRef<R>)>,
+    )>::new(world);
+    let (relations, noitalers) = state.get_mut(world);
+
+    for (source, relations) in &relations {
+        for (target, _) in relations {
+            let die = || {
+                panic!(
+                    "entity: {:?} had relation: {} to target: {:?} which did not have an entry in `Noitaler`",
+                    source,
+                    std::any::type_name::<R>(),
+                    target
+                )
+            };
+
+            let (_, noitalers) = noitalers.get(target).unwrap_or_else(|_| die());
+            if noitalers
+                .into_iter()
+                .all(|noitalers_source| noitalers_source != source)
+            {
+                die();
+            }
+        }
+    }
+
+    for (target, noitalers) in &noitalers {
+        for source in noitalers {
+            let die = || {
+                panic!(
+                    "entity: {:?} had noitaler: {} to source: {:?} which did not have an entry in `Relation`",
+                    target,
+                    std::any::type_name::<R>(),
+                    source,
+                )
+            };
+
+            let (_, relations) = relations.get(source).unwrap_or_else(|_| die());
+            if relations
+                .into_iter()
+                .all(|(relations_target, _)| relations_target != target)
+            {
+                die();
+            }
+        }
+    }
+
+    let edges = relations
+        .iter()
+        .flat_map(|(source, relations)| {
+            relations
+                .into_iter()
+                .map(move |(target, _)| (source, target))
+        })
+        .collect::<Vec<_>>();
+    for (source, target) in edges {
+        if R::Cyclicity::find_cycle(&*world, source, target).is_some() {
+            panic!(
+                "entity: {:?} participates in a cycle with relation kind: {} which disallows cycles",
+                source,
+                std::any::type_name::<R>(),
+            );
+        };
+    }
+}
+
+/// Conformance test-suite for [`Restriction`] implementations, meant to be called from a test
+/// with a kind that uses the restriction as its [`RelKind::SourceRestriction`] and/or
+/// [`RelKind::TargetRestriction`]. The data of each edge is created from the entity on the other
+/// side of it so that mixed up data can be detected.
+///
+/// Inserts, replaces, removes and despawns edges out of one source and into one target, and
+/// refills storages emptied through [`RelationsMut`], panicking if a storage loses or duplicates
+/// edges, mixes up their data, evicts or rejects edges other than the ones it reported, holds
+/// more than one edge despite [`Restriction::AT_MOST_ONE`], or [`assert_relation_graph_good`]
+/// fails.
+pub fn check_restriction<K>()
+where
+    K: RelKind<
+            Target = Entity,
+            DespawnPolicy = Detach,
+            TargetDespawnPolicy = Detach,
+            Symmetry = Directed,
+        > + From<Entity>
+        + PartialEq
+        + Debug,
+{
+    check_source_restriction::<K>();
+    check_target_restriction::<K>();
+    check_emptied_storages::<K>();
+}
+
+/// The targets of `source`, checking that each has the data it was inserted with
+fn source_edges<K>(world: &World, source: Entity) -> Vec<Entity>
+where
+    K: RelKind<Target = Entity> + From<Entity> + PartialEq + Debug,
+{
+    let mut edges = Vec::new();
+    for (target, data) in world
+        .entity(source)
+        .get_all_relations::<K>()
+        .into_iter()
+        .flatten()
+    {
+        assert_eq!(
+            *data,
+            K::from(target),
+            "data was stored for the wrong target"
+        );
+        assert_eq!(
+            world.entity(source).get_relation::<K>(target),
+            Some(data),
+            "`rel_get` disagrees with `rel_iter`"
+        );
+        assert!(
+            !edges.contains(&target),
+            "target {:?} is duplicated",
+            target
+        );
+        edges.push(target);
+    }
+    edges
+}
+
+/// The sources of `target`, checking that each has the data it was inserted with
+fn target_edges<K>(world: &World, target: Entity) -> Vec<Entity>
+where
+    K: RelKind<Target = Entity> + From<Entity> + PartialEq + Debug,
+{
+    let mut edges = Vec::new();
+    for source in world
+        .entity(target)
+        .get_all_noitalers::<K>()
+        .into_iter()
+        .flatten()
+    {
+        assert!(
+            !edges.contains(&source),
+            "source {:?} is duplicated",
+            source
+        );
+        assert_eq!(
+            world.entity(source).get_relation::<K>(target),
+            Some(&K::from(source)),
+            "data was stored for the wrong source"
+        );
+        assert!(
+            world.entity(target).get_noitaler::<K>(source).is_some(),
+            "`noi_contains` disagrees with `noi_iter`"
+        );
+        edges.push(source);
+    }
+    edges
+}
+
+fn assert_same_edges(mut found: Vec<Entity>, expected: &[Entity]) {
+    let mut expected = expected.to_vec();
+    found.sort();
+    expected.sort();
+    assert_eq!(
+        found, expected,
+        "storage does not contain the expected edges"
+    );
+}
+
+fn check_source_restriction<K>()
+where
+    K: RelKind<Target = Entity, DespawnPolicy = Detach, TargetDespawnPolicy = Detach>
+        + From<Entity>
+        + PartialEq
+        + Debug,
+{
+    let mut world = World::new();
+    let source = world.spawn_empty().id();
+    let targets = [(); 8].map(|_| world.spawn_empty().id());
+    let mut expected = Vec::new();
+
+    let insert_all = |world: &mut World, expected: &mut Vec<Entity>| {
+        for &target in &targets {
+            let (to_evict, rejects) = match world.get::<Relation<K>>(source) {
+                Some(rel) => (
+                    K::SourceRestriction::rel_to_evict(&rel.0).copied(),
+                    K::SourceRestriction::rel_rejects(&rel.0, &target),
+                ),
+                None => (None, false),
+            };
+
+            match world
+                .entity_mut(source)
+                .try_insert_relation(K::from(target), target)
+            {
+                Ok(outcome) => {
+                    assert!(!rejects, "`rel_rejects` was true but the insert succeeded");
+                    assert!(outcome.replaced.is_none() && outcome.evicted_source.is_none());
+                    let evicted = outcome.evicted_target.map(|(evicted, data)| {
+                        assert_eq!(data, K::from(evicted), "evicted edge has the wrong data");
+                        evicted
+                    });
+                    assert_eq!(
+                        evicted, to_evict,
+                        "`rel_to_evict` disagrees with `push_rel`"
+                    );
+                    expected.retain(|&target| Some(target) != evicted);
+                    expected.push(target);
+                }
+                Err(RelationError::SourceFull(_)) => {
+                    assert!(rejects, "insert was rejected but `rel_rejects` was false");
+                }
+                Err(e) => panic!("unexpected error: {}", e),
+            }
+            assert_same_edges(source_edges::<K>(world, source), expected);
+            assert!(
+                !K::SourceRestriction::AT_MOST_ONE || expected.len() == 1,
+                "`AT_MOST_ONE` is true but a second edge was inserted without evicting the first"
+            );
+            assert_relation_graph_good::<K>(world);
+        }
+    };
+    insert_all(&mut world, &mut expected);
+    assert!(!expected.is_empty(), "no edge could be inserted");
+
+    // replacing the data of an existing edge never evicts
+    let target = expected[0];
+    let outcome = world
+        .entity_mut(source)
+        .try_insert_relation(K::from(source), target)
+        .unwrap();
+    assert_eq!(outcome.replaced, Some(K::from(target)));
+    assert!(outcome.evicted_target.is_none() && outcome.evicted_source.is_none());
+    *world
+        .entity_mut(source)
+        .get_relation_mut::<K>(target)
+        .unwrap() = K::from(target);
+    assert_same_edges(source_edges::<K>(&world, source), &expected);
+
+    // remove from the middle first so that storages have to keep the remaining edges intact
+    while !expected.is_empty() {
+        let target = expected.remove(expected.len() / 2);
+        world.entity_mut(source).remove_relation::<K>(target);
+        assert!(world.entity(source).get_relation::<K>(target).is_none());
+        assert_same_edges(source_edges::<K>(&world, source), &expected);
+        assert_relation_graph_good::<K>(&mut world);
+    }
+    assert!(world.get::<Relation<K>>(source).is_none());
+
+    insert_all(&mut world, &mut expected);
+    let target = expected.remove(0);
+    world.despawn(target);
+    assert_same_edges(source_edges::<K>(&world, source), &expected);
+    assert_relation_graph_good::<K>(&mut world);
+
+    world.entity_mut(source).clear_relations::<K>();
+    assert!(world.get::<Relation<K>>(source).is_none());
+    assert_relation_graph_good::<K>(&mut world);
+}
+
+fn check_target_restriction<K>()
+where
+    K: RelKind<Target = Entity, DespawnPolicy = Detach, TargetDespawnPolicy = Detach>
+        + From<Entity>
+        + PartialEq
+        + Debug,
+{
+    let mut world = World::new();
+    let target = world.spawn_empty().id();
+    let sources = [(); 8].map(|_| world.spawn_empty().id());
+    let mut expected = Vec::new();
+
+    let insert_all = |world: &mut World, expected: &mut Vec<Entity>| {
+        for &source in &sources {
+            let (to_evict, rejects) = match world.get::<Noitaler<K>>(target) {
+                Some(noi) => (
+                    K::TargetRestriction::noi_to_evict(&noi.0),
+                    K::TargetRestriction::noi_rejects(&noi.0, source),
+                ),
+                None => (None, false),
+            };
+
+            match world
+                .entity_mut(source)
+                .try_insert_relation(K::from(source), target)
+            {
+                Ok(outcome) => {
+                    assert!(!rejects, "`noi_rejects` was true but the insert succeeded");
+                    assert!(outcome.replaced.is_none() && outcome.evicted_target.is_none());
+                    let evicted = outcome.evicted_source.map(|(evicted, data)| {
+                        assert_eq!(data, K::from(evicted), "evicted edge has the wrong data");
+                        evicted
+                    });
+                    assert_eq!(
+                        evicted, to_evict,
+                        "`noi_to_evict` disagrees with `push_noi`"
+                    );
+                    expected.retain(|&source| Some(source) != evicted);
+                    expected.push(source);
+                }
+                Err(RelationError::TargetFull(_)) => {
+                    assert!(rejects, "insert was rejected but `noi_rejects` was false");
+                }
+                Err(e) => panic!("unexpected error: {}", e),
+            }
+            assert_same_edges(target_edges::<K>(world, target), expected);
+            assert!(
+                !K::TargetRestriction::AT_MOST_ONE || expected.len() == 1,
+                "`AT_MOST_ONE` is true but a second edge was inserted without evicting the first"
+            );
+            assert_relation_graph_good::<K>(world);
+        }
+    };
+    insert_all(&mut world, &mut expected);
+    assert!(!expected.is_empty(), "no edge could be inserted");
+
+    // inserting an existing edge again never evicts
+    let source = expected[0];
+    let outcome = world
+        .entity_mut(source)
+        .try_insert_relation(K::from(source), target)
+        .unwrap();
+    assert_eq!(outcome.replaced, Some(K::from(source)));
+    assert!(outcome.evicted_target.is_none() && outcome.evicted_source.is_none());
+    assert_same_edges(target_edges::<K>(&world, target), &expected);
+
+    while !expected.is_empty() {
+        let source = expected.remove(expected.len() / 2);
+        world.entity_mut(source).remove_relation::<K>(target);
+        assert!(world.entity(target).get_noitaler::<K>(source).is_none());
+        assert_same_edges(target_edges::<K>(&world, target), &expected);
+        assert_relation_graph_good::<K>(&mut world);
+    }
+    assert!(world.get::<Noitaler<K>>(target).is_none());
+
+    insert_all(&mut world, &mut expected);
+    let source = expected.remove(0);
+    world.despawn(source);
+    assert_same_edges(target_edges::<K>(&world, target), &expected);
+    assert_relation_graph_good::<K>(&mut world);
+
+    world.entity_mut(target).clear_noitalers::<K>();
+    assert!(world.get::<Noitaler<K>>(target).is_none());
+    assert_relation_graph_good::<K>(&mut world);
+}
+
+fn check_emptied_storages<K>()
+where
+    K: RelKind<Target = Entity, Symmetry = Directed> + From<Entity> + PartialEq + Debug,
+{
+    let mut world = World::new();
+    let [source, target] = [(); 2].map(|_| world.spawn_empty().id());
+    world
+        .entity_mut(source)
+        .insert_relation(K::from(target), target);
+
+    let mut state = SystemState::<RelationsMut<K>>::new(&mut world);
+    let mut relations = state.get_mut(&mut world);
+    assert!(relations.remove(source, target));
+    assert!(
+        relations.targets(source).next().is_none() && relations.sources(target).next().is_none(),
+        "storage still has edges after removing its last edge"
+    );
+    assert!(
+        matches!(
+            relations.insert(source, K::from(target), target),
+            Ok(Applied::Immediately(_))
+        ),
+        "inserting into an emptied storage failed"
+    );
+    assert_eq!(relations.targets(source).collect::<Vec<_>>(), [target]);
+    assert_eq!(relations.sources(target).collect::<Vec<_>>(), [source]);
+    assert_eq!(relations.data(source, target), Some(&K::from(target)));
+    assert!(relations.remove(source, target));
+    assert_relation_graph_good::<K>(&mut world);
+    state.apply(&mut world);
+
+    assert!(world.get::<Relation<K>>(source).is_none());
+    assert!(world.get::<Noitaler<K>>(target).is_none());
+}
@@ -1,9 +1,6 @@
-use bevy::{ecs::world::EntityMut, prelude::Entity};
+use bevy::{prelude::Entity, utils::HashMap};
 
-use crate::{
-    restriction::{Many, One},
-    EntityRefExt, RelKind, Restriction,
-};
+use crate::{traverse::RelationGraph, RelKind, Restriction};
 
 pub struct Cyclic;
 pub struct Acyclic;
@@ -18,53 +15,98 @@ where
     SourceRestriction: Restriction<R>,
     TargetRestriction: Restriction<R>,
 {
-    fn assert_cyclicity(entity: EntityMut<'_>) -> Result<(), ()>;
+    /// Returns the cycle that inserting `source -> target` would introduce, the path starts at
+    /// `source` followed by `target` and ends with the entity that would relate back to `source`.
+    fn find_cycle<G: RelationGraph<R>>(
+        graph: G,
+        source: Entity,
+        target: Entity,
+    ) -> Option<Vec<Entity>>;
 }
 
-fn assert_cyclicity<R: RelKind>(
-    mut entity: EntityMut<'_>,
-    next_step: impl Fn(&mut EntityMut<'_>) -> Option<Entity>,
-) -> Result<(), ()> {
-    let detector_id = entity.id();
-
-    loop {
-        match next_step(&mut entity) {
-            Some(target) if target == detector_id => return Err(()),
-            Some(target) => entity = entity.into_world_mut().entity_mut(target),
-            None => return Ok(()),
-        };
+/// Follows `next_step` from `from` until reaching `to`, returning every entity visited
+fn walk_to(
+    from: Entity,
+    to: Entity,
+    next_step: impl Fn(Entity) -> Option<Entity>,
+) -> Option<Vec<Entity>> {
+    let mut path = vec![from];
+    let mut current = from;
+    while current != to {
+        current = next_step(current)?;
+        path.push(current);
     }
+    Some(path)
 }
 
-impl<R: RelKind> AssertTreeIfAcyclic<R, One, Many> for Acyclic {
-    fn assert_cyclicity(entity: EntityMut<'_>) -> Result<(), ()> {
-        assert_cyclicity::<R>(entity, |entity| {
-            entity
-                .get_all_relations::<R>()
-                .map(|r| r.iter().next().unwrap().0)
-        })
-    }
+fn find_cycle_via_relations<R: RelKind>(
+    graph: impl RelationGraph<R>,
+    source: Entity,
+    target: Entity,
+) -> Option<Vec<Entity>> {
+    let mut path = walk_to(target, source, |entity| graph.first_target(entity))?;
+    path.rotate_right(1);
+    Some(path)
+}
+
+fn find_cycle_via_noitalers<R: RelKind>(
+    graph: impl RelationGraph<R>,
+    source: Entity,
+    target: Entity,
+) -> Option<Vec<Entity>> {
+    let mut path = walk_to(source, target, |entity| graph.first_source(entity))?;
+    path[1..].reverse();
+    Some(path)
 }
-impl<R: RelKind> AssertTreeIfAcyclic<R, Many, One> for Acyclic {
-    fn assert_cyclicity(entity: EntityMut<'_>) -> Result<(), ()> {
-        assert_cyclicity::<R>(entity, |entity| {
-            entity
-                .get_all_noitalers::<R>()
-                .map(|r| r.iter().next().unwrap())
-        })
+
+/// Depth first search over relations from `target` for a path back to `source`, used when
+/// neither side is restricted to at most one edge so there can be more than one path to follow.
+fn find_cycle_via_search<R: RelKind>(
+    graph: impl RelationGraph<R>,
+    source: Entity,
+    target: Entity,
+) -> Option<Vec<Entity>> {
+    let mut parents = HashMap::<Entity, Entity>::default();
+    parents.insert(target, target);
+    let mut stack = vec![target];
+    while let Some(current) = stack.pop() {
+        if current == source {
+            let mut path = vec![source];
+            let mut current = source;
+            while current != target {
+                current = parents[&current];
+                path.push(current);
+            }
+            path.reverse();
+            path.rotate_right(1);
+            return Some(path);
+        }
+
+        graph.for_each_target(current, |next| {
+            if !parents.contains_key(&next) {
+                parents.insert(next, current);
+                stack.push(next);
+            }
+        });
     }
+    None
 }
-impl<R: RelKind> AssertTreeIfAcyclic<R, One, One> for Acyclic {
-    fn assert_cyclicity(entity: EntityMut<'_>) -> Result<(), ()> {
-        assert_cyclicity::<R>(entity, |entity| {
-            entity
-                .get_all_noitalers::<R>()
-                .map(|r| r.iter().next().unwrap())
-        })
+
+impl<R: RelKind, T: Restriction<R>, U: Restriction<R>> AssertTreeIfAcyclic<R, T, U> for Acyclic {
+    fn find_cycle<G: RelationGraph<R>>(
+        graph: G,
+        source: Entity,
+        target: Entity,
+    ) -> Option<Vec<Entity>> {
+        match (T::AT_MOST_ONE, U::AT_MOST_ONE) {
+            (_, true) => find_cycle_via_noitalers::<R>(graph, source, target),
+            (true, false) => find_cycle_via_relations::<R>(graph, source, target),
+            (false, false) => find_cycle_via_search::<R>(graph, source, target),
+        }
     }
 }
 impl<R: RelKind, T: Restriction<R>, U: Restriction<R>> AssertTreeIfAcyclic<R, T, U> for Cyclic {
-    fn assert_cyclicity(_: EntityMut<'_>) -> Result<(), ()> {
-        Ok(())
+    fn find_cycle<G: RelationGraph<R>>(_: G, _: Entity, _: Entity) -> Option<Vec<Entity>> {
+        None
     }
 }
@@ -0,0 +1,146 @@
+use bevy::{
+    ecs::component::NestedDespawns,
+    log::error,
+    prelude::{Entity, World},
+};
+
+use crate::RelKind;
+
+/// Despawning a source also despawns all of its targets, as a [`TargetDespawnPolicy`] despawning
+/// a target also despawns all of its sources.
+pub struct Recursive;
+/// Despawning an entity only removes its edges, the entities on the other side are left alive.
+pub struct Detach;
+/// Entities are expected to have had all their edges removed before being despawned. Despawn hooks
+/// cannot stop a despawn from happening so this logs an error and otherwise behaves like [`Detach`].
+pub struct Deny;
+/// Only usable as a [`TargetDespawnPolicy`], despawning a target makes all of its sources relate
+/// to the despawned target's own target instead (i.e. reparenting to the grandparent). If the
+/// despawned target has multiple targets the first one is used, if it has none this behaves like
+/// [`Detach`].
+pub struct Retarget;
+
+pub trait DespawnPolicy: super::sealed::Sealed {
+    #[doc(hidden)]
+    fn despawn_targets<R: RelKind>(
+        source: Entity,
+        targets: impl Iterator<Item = Entity>,
+        despawner: &mut NestedDespawns<'_>,
+    );
+}
+
+impl DespawnPolicy for Recursive {
+    fn despawn_targets<R: RelKind>(
+        _: Entity,
+        targets: impl Iterator<Item = Entity>,
+        despawner: &mut NestedDespawns<'_>,
+    ) {
+        for target in targets {
+            despawner.despawn(target);
+        }
+    }
+}
+impl DespawnPolicy for Detach {
+    fn despawn_targets<R: RelKind>(
+        _: Entity,
+        _: impl Iterator<Item = Entity>,
+        _: &mut NestedDespawns<'_>,
+    ) {
+    }
+}
+impl DespawnPolicy for Deny {
+    fn despawn_targets<R: RelKind>(
+        source: Entity,
+        targets: impl Iterator<Item = Entity>,
+        _: &mut NestedDespawns<'_>,
+    ) {
+        error!(
+            "Despawned `{:?}` while it still had relations of kind {} to {:?}, the relations have been removed",
+            source,
+            std::any::type_name::<R>(),
+            targets.collect::<Vec<_>>(),
+        );
+    }
+}
+
+pub trait TargetDespawnPolicy: super::sealed::Sealed {
+    /// `sources` have already had their edge to `target` removed, `new_target` is the first
+    /// target of `target` if it had any. Data that is taken out of `sources` will not be
+    /// available from [`RelationEvent::Removed`](crate::RelationEvent::Removed).
+    #[doc(hidden)]
+    fn despawn_sources<R: RelKind>(
+        world: &mut World,
+        target: Entity,
+        new_target: Option<R::Target>,
+        sources: &mut [(Entity, Option<R>)],
+        despawner: &mut NestedDespawns<'_>,
+    );
+}
+
+impl TargetDespawnPolicy for Recursive {
+    fn despawn_sources<R: RelKind>(
+        _: &mut World,
+        _: Entity,
+        _: Option<R::Target>,
+        sources: &mut [(Entity, Option<R>)],
+        despawner: &mut NestedDespawns<'_>,
+    ) {
+        for (source, _) in sources {
+            despawner.despawn(*source);
+        }
+    }
+}
+impl TargetDespawnPolicy for Detach {
+    fn despawn_sources<R: RelKind>(
+        _: &mut World,
+        _: Entity,
+        _: Option<R::Target>,
+        _: &mut [(Entity, Option<R>)],
+        _: &mut NestedDespawns<'_>,
+    ) {
+    }
+}
+impl TargetDespawnPolicy for Deny {
+    fn despawn_sources<R: RelKind>(
+        _: &mut World,
+        target: Entity,
+        _: Option<R::Target>,
+        sources: &mut [(Entity, Option<R>)],
+        _: &mut NestedDespawns<'_>,
+    ) {
+        error!(
+            "Despawned `{:?}` while it was still the target of relations of kind {} from {:?}, the relations have been removed",
+            target,
+            std::any::type_name::<R>(),
+            sources.iter().map(|(source, _)| *source).collect::<Vec<_>>(),
+        );
+    }
+}
+impl TargetDespawnPolicy for Retarget {
+    fn despawn_sources<R: RelKind>(
+        world: &mut World,
+        target: Entity,
+        new_target: Option<R::Target>,
+        sources: &mut [(Entity, Option<R>)],
+        _: &mut NestedDespawns<'_>,
+    ) {
+        let new_target = match new_target {
+            Some(new_target) => new_target,
+            None => return,
+        };
+
+        for (source, data) in sources {
+            let data = data.take().unwrap();
+            if let Err(e) = crate::insert_relation(world, *source, data, new_target.clone()) {
+                error!(
+                    "Could not retarget relation `{:?}` -> {} -> `{:?}` to `{:?}`: {}",
+                    source,
+                    std::any::type_name::<R>(),
+                    target,
+                    new_target,
+                    e,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use bevy::ecs::{prelude::*, system::SystemParam};
+
+use crate::{InsertOutcome, RelKind, RelationTarget, Symmetry};
+
+/// Lifecycle events for edges of kind `T`. Only sent for kinds registered with
+/// [`RelationAppExt::add_relation_events`](crate::RelationAppExt::add_relation_events).
+///
+/// Data is `None` if it was handed out elsewhere, i.e. returned in the [`InsertOutcome`] of
+/// `try_insert_relation` or moved to a new edge by [`Retarget`](crate::despawn_policy::Retarget).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelationEvent<T: RelKind> {
+    /// The edge `source -> target` did not previously exist and was inserted
+    Inserted { source: Entity, target: T::Target },
+    /// The edge `source -> target` already existed and had its data replaced
+    DataReplaced {
+        source: Entity,
+        target: T::Target,
+        old: Option<T>,
+    },
+    /// The edge `source -> target` was removed to uphold a restriction when inserting another edge
+    Evicted {
+        source: Entity,
+        target: T::Target,
+        data: Option<T>,
+    },
+    /// The edge `source -> target` was removed or one of its entities was despawned
+    Removed {
+        source: Entity,
+        target: T::Target,
+        data: Option<T>,
+    },
+}
+
+/// The relation equivalent of [`RemovedComponents`], requires
+/// [`RelationAppExt::add_relation_events`](crate::RelationAppExt::add_relation_events)
+/// to have been called for `T`.
+#[derive(SystemParam)]
+pub struct RemovedRelations<'w, 's, T: RelKind> {
+    events: EventReader<'w, 's, RelationEvent<T>>,
+}
+impl<'w, 's, T: RelKind> RemovedRelations<'w, 's, T> {
+    /// Returns `(source, target, data)` for every edge removed since the system last ran
+    pub fn iter(&mut self) -> impl Iterator<Item = (Entity, T::Target, Option<&T>)> + '_ {
+        self.events.iter().filter_map(|event| match event {
+            RelationEvent::Evicted {
+                source,
+                target,
+                data,
+            }
+            | RelationEvent::Removed {
+                source,
+                target,
+                data,
+            } => Some((*source, target.clone(), data.as_ref())),
+            _ => None,
+        })
+    }
+}
+
+pub(crate) fn send_events<T: RelKind>(
+    world: &mut World,
+    events: impl IntoIterator<Item = RelationEvent<T>>,
+) {
+    if let Some(mut sender) = world.get_resource_mut::<Events<RelationEvent<T>>>() {
+        for event in events {
+            sender.send(event);
+        }
+    }
+}
+
+pub(crate) fn send_removed_events<T: RelKind>(
+    world: &mut World,
+    removed: impl IntoIterator<Item = (Entity, T::Target, Option<T>)>,
+) {
+    send_events(
+        world,
+        removed
+            .into_iter()
+            .map(|(source, target, data)| RelationEvent::Removed {
+                source,
+                target,
+                data,
+            }),
+    );
+}
+
+/// The edges overwritten by an insert along with the data to send in their events
+pub(crate) struct InsertEvents<T: RelKind> {
+    replaced: Option<Option<T>>,
+    evicted_target: Option<(T::Target, Option<T>)>,
+    evicted_source: Option<(Entity, Option<T>)>,
+}
+impl<T: RelKind> InsertOutcome<T> {
+    pub(crate) fn into_events(self) -> InsertEvents<T> {
+        InsertEvents {
+            replaced: self.replaced.map(Some),
+            evicted_target: self
+                .evicted_target
+                .map(|(target, data)| (target, Some(data))),
+            evicted_source: self
+                .evicted_source
+                .map(|(source, data)| (source, Some(data))),
+        }
+    }
+
+    pub(crate) fn events_without_data(&self) -> InsertEvents<T> {
+        InsertEvents {
+            replaced: self.replaced.as_ref().map(|_| None),
+            evicted_target: self
+                .evicted_target
+                .as_ref()
+                .map(|(target, _)| (target.clone(), None)),
+            evicted_source: self
+                .evicted_source
+                .as_ref()
+                .map(|(source, _)| (*source, None)),
+        }
+    }
+}
+
+/// `outcome` is only called if events are enabled for `T` so that kinds without events don't pay
+/// for building them
+pub(crate) fn send_insert_events<T: RelKind>(
+    world: &mut World,
+    source: Entity,
+    target: T::Target,
+    outcome: impl FnOnce() -> InsertEvents<T>,
+) {
+    if world.contains_resource::<Events<RelationEvent<T>>>() {
+        send_events(world, insert_events(source, target, outcome()));
+    }
+}
+
+pub(crate) fn insert_events<T: RelKind>(
+    source: Entity,
+    target: T::Target,
+    outcome: InsertEvents<T>,
+) -> impl Iterator<Item = RelationEvent<T>> {
+    let evicted_target = outcome
+        .evicted_target
+        .map(|(old_target, data)| RelationEvent::Evicted {
+            source,
+            target: old_target,
+            data,
+        });
+    let evicted_source = outcome
+        .evicted_source
+        .map(|(old_source, data)| RelationEvent::Evicted {
+            source: old_source,
+            target: target.clone(),
+            data,
+        });
+    let inserted = match outcome.replaced {
+        Some(old) => RelationEvent::DataReplaced {
+            source,
+            target,
+            old,
+        },
+        None => RelationEvent::Inserted { source, target },
+    };
+
+    let mut events = evicted_target
+        .into_iter()
+        .chain(evicted_source)
+        .chain(std::iter::once(inserted))
+        .collect::<Vec<_>>();
+    if T::Symmetry::SYMMETRIC {
+        let mirrors = events.iter().filter_map(mirror_event).collect::<Vec<_>>();
+        events.extend(mirrors);
+    }
+    events.into_iter()
+}
+
+/// The event for the `target -> source` edge mirroring the edge of `event`, without any data
+fn mirror_event<T: RelKind>(event: &RelationEvent<T>) -> Option<RelationEvent<T>> {
+    let (source, target) = match event {
+        RelationEvent::Inserted { source, target }
+        | RelationEvent::DataReplaced { source, target, .. }
+        | RelationEvent::Evicted { source, target, .. }
+        | RelationEvent::Removed { source, target, .. } => (*source, target.as_entity()?),
+    };
+    if source == target {
+        return None;
+    }
+    let (source, target) = (target, T::Target::from_entity(source)?);
+    Some(match event {
+        RelationEvent::Inserted { .. } => RelationEvent::Inserted { source, target },
+        RelationEvent::DataReplaced { .. } => RelationEvent::DataReplaced {
+            source,
+            target,
+            old: None,
+        },
+        RelationEvent::Evicted { .. } => RelationEvent::Evicted {
+            source,
+            target,
+            data: None,
+        },
+        RelationEvent::Removed { .. } => RelationEvent::Removed {
+            source,
+            target,
+            data: None,
+        },
+    })
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+
+use bevy::{ecs::entity::EntityMap, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{events::send_insert_events, InsertOutcome, RelKind, RelationError, RelationRef};
+
+/// Every edge of one relation kind in a form that can be serialized, independent of scenes.
+/// `D` is what is kept of the data of each edge, e.g. the relation kind itself when it
+/// implements [`Serialize`] or `()` to only keep the shape of the graph.
+///
+/// Nodes are sorted and edges are ordered by their source and then by the order the source
+/// stores them in, so exporting the same graph twice gives the same result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializedGraph<D = ()> {
+    /// Every entity that is the source or target of an edge
+    pub nodes: Vec<Entity>,
+    /// `(source, target, data)` of each edge, the source and target are indices into `nodes`
+    pub edges: Vec<(usize, usize, D)>,
+}
+
+impl<T: RelKind<Target = Entity> + Clone> SerializedGraph<T> {
+    /// Exports every edge of kind `T` along with its data
+    pub fn export(world: &mut World) -> Self {
+        Self::export_with(world, T::clone)
+    }
+
+    /// See [`SerializedGraph::import_with`]
+    pub fn import(
+        &self,
+        world: &mut World,
+        entity_map: &mut EntityMap,
+    ) -> Result<(), GraphImportError> {
+        self.import_with(world, entity_map, T::clone)
+    }
+}
+
+impl<D> SerializedGraph<D> {
+    /// Exports every edge of kind `T`, keeping `f` of its data
+    pub fn export_with<T, F>(world: &mut World, mut f: F) -> Self
+    where
+        T: RelKind<Target = Entity>,
+        F: FnMut(&T) -> D,
+    {
+        let mut query = world.query::<(Entity, RelationRef<T>)>();
+        let mut sources = query.iter(world).collect::<Vec<_>>();
+        sources.sort_by_key(|(source, _)| *source);
+
+        let mut nodes = sources
+            .iter()
+            .flat_map(|(source, relations)| {
+                std::iter::once(*source).chain(relations.into_iter().map(|(target, _)| target))
+            })
+            .collect::<Vec<_>>();
+        nodes.sort();
+        nodes.dedup();
+        let index = |entity| nodes.binary_search(&entity).unwrap();
+
+        let edges = sources
+            .iter()
+            .flat_map(|(source, relations)| {
+                relations
+                    .into_iter()
+                    .map(move |(target, data)| (*source, target, data))
+            })
+            .map(|(source, target, data)| (index(source), index(target), f(data)))
+            .collect();
+
+        SerializedGraph { nodes, edges }
+    }
+
+    /// Inserts every edge into `world` as if by [`EntityMutExt::try_insert_relation`] so
+    /// restrictions, cyclicity and despawn policies apply as usual. `T` is created from the data
+    /// of each edge with `f`. Nodes are spawned into the world through `entity_map` like when
+    /// spawning a [`DynamicScene`], nodes that are already in the map are reused.
+    ///
+    /// The import is all or nothing. Edges referring to missing nodes are caught before anything
+    /// is spawned, and if an edge can't be inserted every edge inserted before it is removed
+    /// again, evicted edges are restored, and nodes spawned by the import are despawned and
+    /// removed from `entity_map`. Restored edges may end up in a different order than before.
+    /// [`RelationEvent`](crate::RelationEvent)s are only sent once every edge was inserted.
+    ///
+    /// [`EntityMutExt::try_insert_relation`]: crate::EntityMutExt::try_insert_relation
+    pub fn import_with<T, F>(
+        &self,
+        world: &mut World,
+        entity_map: &mut EntityMap,
+        mut f: F,
+    ) -> Result<(), GraphImportError>
+    where
+        T: RelKind<Target = Entity>,
+        F: FnMut(&D) -> T,
+    {
+        if let Some(edge) = self
+            .edges
+            .iter()
+            .position(|&(source, target, _)| source.max(target) >= self.nodes.len())
+        {
+            return Err(GraphImportError::MissingNode(edge));
+        }
+
+        let mut spawned = Vec::new();
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|&node| {
+                *entity_map.entry(node).or_insert_with(|| {
+                    spawned.push(node);
+                    world.spawn_empty().id()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut inserted = Vec::with_capacity(self.edges.len());
+        for (edge, &(source, target, ref data)) in self.edges.iter().enumerate() {
+            let (source, target) = (nodes[source], nodes[target]);
+            // nodes reused from `entity_map` may have been despawned since
+            let result = match world.get_entity(source) {
+                Some(_) => crate::try_insert_relation(world, source, f(data), target),
+                None => Err(RelationError::DeadSource(source)),
+            };
+            match result {
+                Ok(outcome) => inserted.push((source, target, outcome)),
+                Err(error) => {
+                    for (source, target, outcome) in inserted.into_iter().rev() {
+                        undo_insert(world, source, target, outcome);
+                    }
+                    for node in spawned {
+                        let entity = entity_map.remove(node).unwrap();
+                        world.despawn(entity);
+                    }
+                    return Err(GraphImportError::Relation(edge, error));
+                }
+            }
+        }
+
+        for (source, target, outcome) in inserted {
+            send_insert_events(world, source, target, || outcome.into_events());
+        }
+        Ok(())
+    }
+}
+
+/// Restores the edges of kind `T` to how they were before `source -> target` was inserted
+fn undo_insert<T: RelKind<Target = Entity>>(
+    world: &mut World,
+    source: Entity,
+    target: Entity,
+    outcome: InsertOutcome<T>,
+) {
+    let restore = |world: &mut World, source, data, target| {
+        crate::try_insert_relation(world, source, data, target)
+            .expect("edges that existed before the import can be inserted again");
+    };
+
+    match outcome.replaced {
+        Some(data) => restore(world, source, data, target),
+        None => {
+            crate::remove_edge::<T>(world, source, &target);
+            crate::remove_mirror::<T>(world, source, &target);
+        }
+    }
+    if let Some((old_target, data)) = outcome.evicted_target {
+        restore(world, source, data, old_target);
+    }
+    if let Some((old_source, data)) = outcome.evicted_source {
+        restore(world, old_source, data, target);
+    }
+}
+
+/// Why [`SerializedGraph::import_with`] failed, nothing was imported
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphImportError {
+    /// The edge at this index refers to a node past the end of [`SerializedGraph::nodes`]
+    MissingNode(usize),
+    /// The edge at this index could not be inserted
+    Relation(usize, RelationError),
+}
+impl fmt::Display for GraphImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphImportError::MissingNode(edge) => {
+                write!(f, "edge {} refers to a node that does not exist", edge)
+            }
+            GraphImportError::Relation(edge, error) => {
+                write!(f, "edge {} could not be inserted: {}", edge, error)
+            }
+        }
+    }
+}
+impl std::error::Error for GraphImportError {}
@@ -0,0 +1,175 @@
+use bevy::{
+    app::{App, CoreStage, Plugin},
+    ecs::prelude::*,
+    hierarchy::{BuildChildren, BuildWorldChildren, Children, Parent},
+    log::warn,
+    utils::HashSet,
+};
+
+use crate::{
+    cyclicity::Acyclic,
+    despawn_policy::{Detach, Recursive},
+    restriction::{Many, One},
+    symmetry::Directed,
+    EntityCommandsExt, RelKind, RelationAppExt, RelationEvent, Relations,
+};
+
+/// A relation equivalent of bevy's [`Parent`]/[`Children`] hierarchy, the source is the child.
+/// Despawning the parent despawns its children like `despawn_recursive` does.
+/// See [`HierarchyMirrorPlugin`] for keeping both in sync.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChildOf;
+
+impl RelKind for ChildOf {
+    type Target = Entity;
+    type SourceRestriction = One;
+    type TargetRestriction = Many;
+    type Cyclicity = Acyclic;
+    type DespawnPolicy = Detach;
+    type TargetDespawnPolicy = Recursive;
+    type Symmetry = Directed;
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
+pub enum HierarchyMirrorStage {
+    /// Updates [`ChildOf`] relations from [`Parent`] components that were changed or removed
+    HierarchyToRelations,
+    /// Updates [`Parent`]/[`Children`] from [`ChildOf`] relations that were inserted or removed
+    RelationsToHierarchy,
+}
+
+/// Mirrors edits between [`ChildOf`] relations and [`Parent`]/[`Children`] in two stages that
+/// run after [`CoreStage::Update`]. If both were changed to disagree in the same frame the
+/// hierarchy wins. Parents that can't be mirrored, e.g. because they would form a cycle, log a
+/// warning and leave the relation unchanged.
+///
+/// Every existing `Parent` counts as changed the first time the plugin runs so this also migrates
+/// any hierarchy spawned before it was added, see [`migrate_hierarchy`] for doing so immediately.
+pub struct HierarchyMirrorPlugin;
+
+impl Plugin for HierarchyMirrorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_relation_events::<ChildOf>()
+            .add_stage_after(
+                CoreStage::Update,
+                HierarchyMirrorStage::HierarchyToRelations,
+                SystemStage::parallel(),
+            )
+            .add_stage_after(
+                HierarchyMirrorStage::HierarchyToRelations,
+                HierarchyMirrorStage::RelationsToHierarchy,
+                SystemStage::parallel(),
+            )
+            .add_system_to_stage(
+                HierarchyMirrorStage::HierarchyToRelations,
+                hierarchy_to_relations,
+            )
+            .add_system_to_stage(
+                HierarchyMirrorStage::RelationsToHierarchy,
+                relations_to_hierarchy,
+            );
+    }
+}
+
+/// Inserts a [`ChildOf`] relation for every entity with a [`Parent`], replacing any `ChildOf`
+/// relation to a different parent. Parents that can't be mirrored log a warning.
+pub fn migrate_hierarchy(world: &mut World) {
+    let mut query = world.query::<(Entity, &Parent)>();
+    let parents = query
+        .iter(world)
+        .map(|(child, parent)| (child, parent.get()))
+        .collect::<Vec<_>>();
+
+    for (child, parent) in parents {
+        if let Err(e) = crate::insert_relation(world, child, ChildOf, parent) {
+            warn!(
+                "Could not migrate parent of `{:?}` to `{:?}`: {}",
+                child, parent, e
+            );
+        }
+    }
+}
+
+fn relations_to_hierarchy(
+    mut commands: Commands,
+    mut events: EventReader<RelationEvent<ChildOf>>,
+    relations: Relations<ChildOf>,
+    parents: Query<&Parent>,
+    children: Query<&Children>,
+) {
+    let mut sources = HashSet::new();
+    for event in events.iter() {
+        match event {
+            RelationEvent::Inserted { source, target }
+            | RelationEvent::Evicted { source, target, .. }
+            | RelationEvent::Removed { source, target, .. } => {
+                sources.insert((*source, *target));
+            }
+            RelationEvent::DataReplaced { .. } => (),
+        }
+    }
+
+    for (source, target) in sources {
+        if commands.get_entity(source).is_none() {
+            if children.get(target).is_ok_and(|c| c.contains(&source)) {
+                commands
+                    .add(move |world: &mut World| remove_despawned_child(world, target, source));
+            }
+            continue;
+        }
+
+        let new_parent = relations.targets(source).next();
+        if new_parent == parents.get(source).ok().map(Parent::get) {
+            continue;
+        }
+        match new_parent {
+            Some(parent) => commands.entity(parent).add_child(source),
+            None => commands.entity(source).remove_parent(),
+        };
+    }
+}
+
+// `Children` can't be edited directly so it is rebuilt without the despawned child
+fn remove_despawned_child(world: &mut World, parent: Entity, child: Entity) {
+    let mut remaining = match world.get_entity_mut(parent) {
+        Some(mut parent) => match parent.remove::<Children>() {
+            Some(children) => children.to_vec(),
+            None => return,
+        },
+        None => return,
+    };
+    // other children may have been despawned in the same frame
+    remaining.retain(|&c| c != child && world.get_entity(c).is_some());
+    if remaining.is_empty() {
+        return;
+    }
+    for &child in &remaining {
+        world.entity_mut(child).remove::<Parent>();
+    }
+    world.entity_mut(parent).push_children(&remaining);
+}
+
+fn hierarchy_to_relations(
+    mut commands: Commands,
+    changed: Query<(Entity, &Parent), Changed<Parent>>,
+    removed: RemovedComponents<Parent>,
+    parents: Query<(), With<Parent>>,
+    relations: Relations<ChildOf>,
+) {
+    for (child, parent) in &changed {
+        if !relations.has(child, parent.get()) {
+            commands
+                .entity(child)
+                .try_insert_relation(ChildOf, parent.get());
+        }
+    }
+
+    for child in removed.iter() {
+        if commands.get_entity(child).is_none() || parents.contains(child) {
+            continue;
+        }
+        if let Some(parent) = relations.targets(child).next() {
+            commands.entity(child).remove_relation::<ChildOf>(parent);
+        }
+    }
+}
@@ -6,13 +6,13 @@ pub struct RelationIter<'a, T: RelKind> {
     data: <T::SourceRestriction as Restriction<T>>::RelDataIter<'a>,
 }
 impl<'a, T: RelKind> Iterator for RelationIter<'a, T> {
-    type Item = (Entity, &'a T);
+    type Item = (T::Target, &'a T);
     fn next(&mut self) -> Option<Self::Item> {
-        Some((self.targets.next()?, self.data.next()?))
+        Some((self.targets.next()?.clone(), self.data.next()?))
     }
 }
 impl<'a, T: RelKind> IntoIterator for &'a RelationRefItem<'_, T> {
-    type Item = (Entity, &'a T);
+    type Item = (T::Target, &'a T);
     type IntoIter = RelationIter<'a, T>;
 
     fn into_iter(self) -> RelationIter<'a, T> {
@@ -26,7 +26,7 @@ impl<T: RelKind> RelationRefItem<'_, T> {
     }
 }
 impl<'a, T: RelKind> IntoIterator for &'a RelationMutItem<'_, T> {
-    type Item = (Entity, &'a T);
+    type Item = (T::Target, &'a T);
     type IntoIter = RelationIter<'a, T>;
 
     fn into_iter(self) -> RelationIter<'a, T> {
@@ -40,7 +40,7 @@ impl<T: RelKind> RelationMutItem<'_, T> {
     }
 }
 impl<'a, T: RelKind> IntoIterator for RelationRefItem<'a, T> {
-    type Item = (Entity, &'a T);
+    type Item = (T::Target, &'a T);
     type IntoIter = RelationIter<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
@@ -54,13 +54,13 @@ pub struct RelationIterMut<'a, T: RelKind> {
     data: <T::SourceRestriction as Restriction<T>>::RelDataIterMut<'a>,
 }
 impl<'a, T: RelKind> Iterator for RelationIterMut<'a, T> {
-    type Item = (Entity, &'a mut T);
-    fn next(&mut self) -> Option<(Entity, &'a mut T)> {
-        Some((self.targets.next()?, self.data.next()?))
+    type Item = (T::Target, &'a mut T);
+    fn next(&mut self) -> Option<(T::Target, &'a mut T)> {
+        Some((self.targets.next()?.clone(), self.data.next()?))
     }
 }
 impl<'a, T: RelKind> IntoIterator for &'a mut RelationMutItem<'_, T> {
-    type Item = (Entity, &'a mut T);
+    type Item = (T::Target, &'a mut T);
     type IntoIter = RelationIterMut<'a, T>;
 
     fn into_iter(self) -> RelationIterMut<'a, T> {
@@ -70,7 +70,7 @@ impl<'a, T: RelKind> IntoIterator for &'a mut RelationMutItem<'_, T> {
     }
 }
 impl<'a, T: RelKind> IntoIterator for RelationMutItem<'a, T> {
-    type Item = (Entity, &'a mut T);
+    type Item = (T::Target, &'a mut T);
     type IntoIter = RelationIterMut<'a, T>;
 
     fn into_iter(self) -> RelationIterMut<'a, T> {
@@ -87,7 +87,7 @@ impl<T: RelKind> RelationMutItem<'_, T> {
 }
 
 pub struct NoitalerIter<'a, T: RelKind> {
-    targets: <T::TargetRestriction as Restriction<T>>::NoiTargetIter<'a>,
+    targets: <T::TargetRestriction as Restriction<T>>::NoiSourceIter<'a>,
 }
 impl<'a, T: RelKind> Iterator for NoitalerIter<'a, T> {
     type Item = Entity;
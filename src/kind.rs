@@ -0,0 +1,79 @@
+/// Implements [`RelKind`](crate::RelKind) with defaults for the associated types that were added
+/// after `SourceRestriction`, `TargetRestriction` and `Cyclicity`, since associated types can't
+/// have defaults on stable. Leaving them out gives the behaviour relation kinds had before they
+/// were configurable:
+///
+/// - `Target = Entity`
+/// - `DespawnPolicy = despawn_policy::Recursive`
+/// - `TargetDespawnPolicy = despawn_policy::Detach`
+/// - `Symmetry = symmetry::Directed`
+///
+/// ```ignore
+/// relation_kind! {
+///     impl RelKind for InGroup {
+///         type SourceRestriction = One;
+///         type TargetRestriction = Many;
+///         type Cyclicity = Cyclic;
+///         type DespawnPolicy = Detach;
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! relation_kind {
+    (impl RelKind for $kind:ty { $($items:tt)* }) => {
+        $crate::relation_kind! {
+            @munch $kind;
+            {$crate::kind::__Entity}
+            {}
+            {}
+            {}
+            {$crate::despawn_policy::Recursive}
+            {$crate::despawn_policy::Detach}
+            {$crate::symmetry::Directed};
+            $($items)*
+        }
+    };
+
+    (@munch $kind:ty; $t:tt $s:tt $tr:tt $c:tt $d:tt $td:tt $sy:tt; type Target = $v:ty; $($rest:tt)*) => {
+        $crate::relation_kind! { @munch $kind; {$v} $s $tr $c $d $td $sy; $($rest)* }
+    };
+    (@munch $kind:ty; $t:tt $s:tt $tr:tt $c:tt $d:tt $td:tt $sy:tt; type SourceRestriction = $v:ty; $($rest:tt)*) => {
+        $crate::relation_kind! { @munch $kind; $t {$v} $tr $c $d $td $sy; $($rest)* }
+    };
+    (@munch $kind:ty; $t:tt $s:tt $tr:tt $c:tt $d:tt $td:tt $sy:tt; type TargetRestriction = $v:ty; $($rest:tt)*) => {
+        $crate::relation_kind! { @munch $kind; $t $s {$v} $c $d $td $sy; $($rest)* }
+    };
+    (@munch $kind:ty; $t:tt $s:tt $tr:tt $c:tt $d:tt $td:tt $sy:tt; type Cyclicity = $v:ty; $($rest:tt)*) => {
+        $crate::relation_kind! { @munch $kind; $t $s $tr {$v} $d $td $sy; $($rest)* }
+    };
+    (@munch $kind:ty; $t:tt $s:tt $tr:tt $c:tt $d:tt $td:tt $sy:tt; type DespawnPolicy = $v:ty; $($rest:tt)*) => {
+        $crate::relation_kind! { @munch $kind; $t $s $tr $c {$v} $td $sy; $($rest)* }
+    };
+    (@munch $kind:ty; $t:tt $s:tt $tr:tt $c:tt $d:tt $td:tt $sy:tt; type TargetDespawnPolicy = $v:ty; $($rest:tt)*) => {
+        $crate::relation_kind! { @munch $kind; $t $s $tr $c $d {$v} $sy; $($rest)* }
+    };
+    (@munch $kind:ty; $t:tt $s:tt $tr:tt $c:tt $d:tt $td:tt $sy:tt; type Symmetry = $v:ty; $($rest:tt)*) => {
+        $crate::relation_kind! { @munch $kind; $t $s $tr $c $d $td {$v}; $($rest)* }
+    };
+
+    (@munch $kind:ty; {$($t:tt)+} {$($s:tt)+} {$($tr:tt)+} {$($c:tt)+} {$($d:tt)+} {$($td:tt)+} {$($sy:tt)+};) => {
+        impl $crate::RelKind for $kind {
+            type Target = $($t)+;
+            type SourceRestriction = $($s)+;
+            type TargetRestriction = $($tr)+;
+            type Cyclicity = $($c)+;
+            type DespawnPolicy = $($d)+;
+            type TargetDespawnPolicy = $($td)+;
+            type Symmetry = $($sy)+;
+        }
+    };
+    (@munch $kind:ty; $($slots:tt)*) => {
+        compile_error!(
+            "`relation_kind!` requires `SourceRestriction`, `TargetRestriction` and `Cyclicity`, \
+             and only accepts the associated types of `RelKind`"
+        );
+    };
+}
+
+#[doc(hidden)]
+pub use bevy::ecs::entity::Entity as __Entity;
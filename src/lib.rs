@@ -1,136 +1,443 @@
 use bevy::ecs::{
-    component::TableStorage,
+    component::{NestedDespawns, TableStorage},
     prelude::*,
     system::Command,
     world::{EntityMut, EntityRef},
 };
+use std::{cmp::Ordering, fmt, marker::PhantomData};
 
 #[cfg(test)]
 mod testl;
 
+pub mod aggregate;
+pub mod app;
+pub mod conformance;
 pub mod cyclicity;
+pub mod despawn_policy;
+pub mod events;
+pub mod graph;
+pub mod hierarchy;
 pub mod iter;
+#[doc(hidden)]
+pub mod kind;
+pub mod propagate;
+pub mod query;
+pub mod reflect;
+pub mod relations;
 pub mod restriction;
+pub mod scene;
+pub mod symmetry;
+pub mod target;
+pub mod topo;
+pub mod traverse;
 
 use cyclicity::AssertTreeIfAcyclic;
+use events::{send_insert_events, send_removed_events};
+use restriction::OrderedRestriction;
 
 pub use restriction::Restriction;
+pub use target::RelationTarget;
 pub use world_queries::{
-    NoitalerRef, NoitalerRefItem, RelationMut, RelationMutItem, RelationMutReadOnly as RelationRef,
+    AddedRelation, ChangedNoitaler, ChangedRelation, ChangedRelationData, NoitalerRef,
+    NoitalerRefItem, RelationMut, RelationMutItem, RelationMutReadOnly as RelationRef,
     RelationMutReadOnlyItem as RelationRefItem, WithRelation, WithoutRelation,
 };
 
 pub use cyclicity::Cyclicity;
+pub use despawn_policy::{DespawnPolicy, TargetDespawnPolicy};
+pub use symmetry::Symmetry;
 
+pub use aggregate::{AggregateOp, AggregatePlugin, AggregateSource, AggregateTarget};
+pub use app::RelationAppExt;
 pub use commands::EntityCommandsExt;
+pub use events::{RelationEvent, RemovedRelations};
+pub use graph::{GraphImportError, SerializedGraph};
+pub use hierarchy::{migrate_hierarchy, ChildOf, HierarchyMirrorPlugin};
+pub use propagate::{Propagate, PropagatePlugin};
+pub use query::{RelatesTo, TargetedBy};
+pub use reflect::{relation_kinds, ReflectRelation};
+pub use relations::{Applied, Relations, RelationsMut};
+pub use scene::{
+    dynamic_scene_with_relations, load_scene_relations, relations_into_scene_world, SceneRelation,
+};
+pub use topo::TopoOrder;
+pub use traverse::Traverse;
 
+/// Describes a kind of relation, see [`relation_kind!`] for implementing it with defaults for
+/// most associated types.
 pub trait RelKind: Sized + Send + Sync + 'static {
+    /// What relations of kind `Self` point to, this is usually `Entity`. Edges to any other
+    /// [`RelationTarget`] are not tracked on the target side and are unaffected by despawns.
+    type Target: RelationTarget;
     /// Number of relations of kind `Self` allowed on a source entity
     type SourceRestriction: Restriction<Self>;
     /// Number of relations of kind `Self` allowed to point to a target entity
     type TargetRestriction: Restriction<Self>;
 
-    /// Whether cycles are allowed in the graph created by edges of `Self`. This can only be
-    /// set to [`cyclicity::Acyclic`] if either [`Self::SourceRestriction`] or [`Self::TargetRestriction`] is set to [`restriction::One`]
+    /// Whether cycles are allowed in the graph created by edges of `Self`. Checking for cycles is
+    /// cheapest when either [`Self::SourceRestriction`] or [`Self::TargetRestriction`] allows at
+    /// most one edge, otherwise inserting an edge searches every path out of its target.
     type Cyclicity: Cyclicity
         + cyclicity::AssertTreeIfAcyclic<Self, Self::SourceRestriction, Self::TargetRestriction>;
+
+    /// What happens to the targets of a source entity when it is despawned, see
+    /// [`despawn_policy::Recursive`], [`despawn_policy::Detach`] and [`despawn_policy::Deny`]
+    type DespawnPolicy: DespawnPolicy;
+    /// What happens to the sources of a target entity when it is despawned, see
+    /// [`despawn_policy::Recursive`], [`despawn_policy::Detach`], [`despawn_policy::Retarget`]
+    /// and [`despawn_policy::Deny`]
+    type TargetDespawnPolicy: TargetDespawnPolicy;
+    /// Whether edges of `Self` have a direction, see [`symmetry::Directed`] and
+    /// [`symmetry::Symmetric`]
+    type Symmetry: Symmetry<Self>;
 }
 
+// The change ticks of `Relation<T>` track changes to relation data, changes to the set of
+// edges are tracked by the ticks of `RelationEdges<T>` which always exists alongside it.
 struct Relation<T: RelKind>(<T::SourceRestriction as Restriction<T>>::RelStorage);
 #[derive(Component)]
+struct RelationEdges<T: RelKind>(PhantomData<T>);
 struct Noitaler<T: RelKind>(<T::TargetRestriction as Restriction<T>>::NoiStorage);
 
 impl<T: RelKind> Component for Relation<T> {
     type Storage = TableStorage;
 
-    fn despawn_hook() -> fn(Entity, &mut World, bevy::ecs::component::NestedDespawns<'_>)
+    fn despawn_hook() -> fn(Entity, &mut World, NestedDespawns<'_>)
     where
         Self: Sized,
     {
-        |e, world, mut despawner| {
-            let mut entity = world.entity_mut(e);
-            let mut rel = entity.remove::<Relation<T>>().unwrap();
-            let noi = entity.remove::<Noitaler<T>>();
-            for target in T::SourceRestriction::rel_iter(&mut rel.0).1 {
-                let mut target_thing = world.entity_mut(target);
-                let mut noi = target_thing.get_mut::<Noitaler<T>>().unwrap();
-
-                if T::TargetRestriction::remove_noi(&mut noi.0, e) {
-                    target_thing.remove::<Noitaler<T>>();
-                }
+        despawn_hook::<T>
+    }
+}
+
+impl<T: RelKind> Component for Noitaler<T> {
+    type Storage = TableStorage;
+
+    fn despawn_hook() -> fn(Entity, &mut World, NestedDespawns<'_>)
+    where
+        Self: Sized,
+    {
+        despawn_hook::<T>
+    }
+}
+
+/// Shared by `Relation<T>` and `Noitaler<T>`, whichever hook runs first handles both sides of `e`
+/// so that the targets of `e` are still known when applying [`RelKind::TargetDespawnPolicy`].
+fn despawn_hook<T: RelKind>(e: Entity, world: &mut World, mut despawner: NestedDespawns<'_>) {
+    let targets = clear_relations::<T>(world, e);
+    let mut sources = clear_noitalers::<T>(world, e)
+        .into_iter()
+        .map(|(source, data)| (source, Some(data)))
+        .collect::<Vec<_>>();
+
+    if !sources.is_empty() {
+        let new_target = targets
+            .iter()
+            .map(|(target, _)| target)
+            .find(|target| target.as_entity() != Some(e))
+            .cloned();
+        T::TargetDespawnPolicy::despawn_sources(world, e, new_target, &mut sources, &mut despawner);
+        let target = T::Target::from_entity(e).unwrap();
+        send_removed_events(
+            world,
+            sources
+                .into_iter()
+                .map(|(source, data)| (source, target.clone(), data)),
+        );
+    }
 
-                // FIXME support non recursive despawns
-                despawner.despawn(target);
+    if !targets.is_empty() {
+        let target_ids = targets
+            .iter()
+            .filter_map(|(target, _)| target.as_entity())
+            .collect::<Vec<_>>();
+        send_removed_events(
+            world,
+            targets
+                .into_iter()
+                .map(|(target, data)| (e, target, Some(data))),
+        );
+        T::DespawnPolicy::despawn_targets::<T>(e, target_ids.into_iter(), &mut despawner);
+    }
+}
+
+/// Removes `target` from the `Relation<T>` of `source` without updating the `Noitaler<T>` of `target`
+fn remove_rel_entry<T: RelKind>(
+    world: &mut World,
+    source: Entity,
+    target: &T::Target,
+) -> Option<T> {
+    let mut source = world.entity_mut(source);
+    let mut rel = source.get_mut::<Relation<T>>()?;
+    // removing an edge does not change the data of the remaining edges
+    let (data, is_empty) =
+        T::SourceRestriction::remove_rel(&mut rel.bypass_change_detection().0, target)?;
+    match is_empty {
+        true => drop(source.remove::<(Relation<T>, RelationEdges<T>)>()),
+        false => source.get_mut::<RelationEdges<T>>().unwrap().set_changed(),
+    }
+    Some(data)
+}
+
+/// Removes `source` from the `Noitaler<T>` of `target` without updating the `Relation<T>` of `source`
+fn remove_noi_entry<T: RelKind>(world: &mut World, target: Entity, source: Entity) -> bool {
+    let mut target = world.entity_mut(target);
+    let removed = match target.get_mut::<Noitaler<T>>() {
+        Some(mut noi) => T::TargetRestriction::remove_noi(&mut noi.0, source),
+        None => None,
+    };
+    if let Some(true) = removed {
+        target.remove::<Noitaler<T>>();
+    }
+    removed.is_some()
+}
+
+/// Removes the edge `source -> target` from both of its entities
+fn remove_edge<T: RelKind>(world: &mut World, source: Entity, target: &T::Target) -> Option<T> {
+    let data = remove_rel_entry::<T>(world, source, target)?;
+    if let Some(target) = target.as_entity() {
+        remove_noi_entry::<T>(world, target, source);
+    }
+    Some(data)
+}
+
+/// Removes the `target -> source` edge mirroring `source -> target` if `T` is
+/// [`symmetry::Symmetric`], returning it as `(source, target, data)` of the mirror
+fn remove_mirror<T: RelKind>(
+    world: &mut World,
+    source: Entity,
+    target: &T::Target,
+) -> Option<(Entity, T::Target, Option<T>)> {
+    if !T::Symmetry::SYMMETRIC {
+        return None;
+    }
+    let target = target.as_entity().filter(|&target| target != source)?;
+    let source = T::Target::from_entity(source).unwrap();
+    let data = remove_edge::<T>(world, target, &source)?;
+    Some((target, source, Some(data)))
+}
+
+/// Removes every edge out of `source` returning the targets and data of the removed edges
+fn clear_relations<T: RelKind>(world: &mut World, source: Entity) -> Vec<(T::Target, T)> {
+    let rel = match world
+        .entity_mut(source)
+        .remove::<(Relation<T>, RelationEdges<T>)>()
+    {
+        Some((rel, _)) => rel,
+        None => return Vec::new(),
+    };
+
+    T::SourceRestriction::rel_into_iter(rel.0)
+        .map(|(data, target)| {
+            if let Some(target) = target.as_entity() {
+                remove_noi_entry::<T>(world, target, source);
             }
+            (target, data)
+        })
+        .collect()
+}
+
+/// Removes every edge into `target` returning the sources and data of the removed edges
+fn clear_noitalers<T: RelKind>(world: &mut World, target: Entity) -> Vec<(Entity, T)> {
+    let noi = match world.entity_mut(target).remove::<Noitaler<T>>() {
+        Some(noi) => noi,
+        None => return Vec::new(),
+    };
+
+    // a `Noitaler<T>` only exists for kinds that target entities
+    let target = T::Target::from_entity(target).unwrap();
+    T::TargetRestriction::noi_iter(&noi.0)
+        .map(|source| (source, remove_rel_entry(world, source, &target).unwrap()))
+        .collect()
+}
+
+fn clear_relations_with_events<T: RelKind>(world: &mut World, source: Entity) {
+    let removed = clear_relations::<T>(world, source);
+    send_removed_events(
+        world,
+        removed
+            .into_iter()
+            .map(|(target, data)| (source, target, Some(data))),
+    );
+}
 
-            if let Some(mut noi) = noi {
-                for source in T::TargetRestriction::noi_iter(&mut noi.0) {
-                    let mut source = world.entity_mut(source);
-                    let mut rel = source.get_mut::<Relation<T>>().unwrap();
+fn clear_noitalers_with_events<T: RelKind>(world: &mut World, target_id: Entity) {
+    let removed = clear_noitalers::<T>(world, target_id);
+    if let Some(target) = T::Target::from_entity(target_id) {
+        send_removed_events(
+            world,
+            removed
+                .into_iter()
+                .map(|(source, data)| (source, target.clone(), Some(data))),
+        );
+    }
+}
 
-                    if T::SourceRestriction::remove_rel(&mut rel.0, e) {
-                        source.remove::<Relation<T>>();
-                    }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelationError {
+    /// The source entity does not exist
+    DeadSource(Entity),
+    /// The target entity does not exist
+    DeadTarget(Entity),
+    /// The relation kind is [`cyclicity::Acyclic`] and the source and target are the same entity
+    SelfEdge(Entity),
+    /// The relation kind is [`cyclicity::Acyclic`] and the edge would have introduced a cycle.
+    /// The path starts at the source followed by the target and ends with the entity that
+    /// relates back to the source.
+    Cycle(Vec<Entity>),
+    /// The source already has as many edges as [`RelKind::SourceRestriction`] allows and it
+    /// rejects further inserts, see [`restriction::Reject`]
+    SourceFull(Entity),
+    /// The target already has as many edges as [`RelKind::TargetRestriction`] allows and it
+    /// rejects further inserts, see [`restriction::Reject`]
+    TargetFull(Entity),
+}
+impl fmt::Display for RelationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelationError::DeadSource(source) => {
+                write!(f, "source `{:?}` does not exist", source)
+            }
+            RelationError::DeadTarget(target) => {
+                write!(f, "target `{:?}` does not exist", target)
+            }
+            RelationError::SelfEdge(entity) => {
+                write!(f, "`{:?}` relates to itself which is a cycle", entity)
+            }
+            RelationError::Cycle(path) => {
+                write!(f, "introduces a cycle: ")?;
+                for entity in path {
+                    write!(f, "`{:?}` -> ", entity)?;
                 }
+                write!(f, "`{:?}`", path[0])
+            }
+            RelationError::SourceFull(source) => {
+                write!(f, "source `{:?}` cannot have any more relations", source)
+            }
+            RelationError::TargetFull(target) => {
+                write!(
+                    f,
+                    "target `{:?}` cannot be targeted by any more relations",
+                    target
+                )
             }
         }
     }
 }
+impl std::error::Error for RelationError {}
 
-trait EntityMutExtInternal {
-    fn get_or_insert_with<T: Component>(&mut self, with: impl FnOnce() -> T) -> Mut<'_, T>;
-}
-impl EntityMutExtInternal for EntityMut<'_> {
-    fn get_or_insert_with<T: Component>(&mut self, with: impl FnOnce() -> T) -> Mut<'_, T> {
-        if let None = self.get_mut::<T>() {
-            self.insert(with());
+/// Checks the restrictions of `T` that reject inserts instead of evicting edges
+fn check_rejected<T: RelKind>(
+    rel: Option<&Relation<T>>,
+    noi: Option<&Noitaler<T>>,
+    source: Entity,
+    target: &T::Target,
+) -> Result<(), RelationError> {
+    if rel.is_some_and(|rel| T::SourceRestriction::rel_rejects(&rel.0, target)) {
+        return Err(RelationError::SourceFull(source));
+    }
+    match (noi, target.as_entity()) {
+        (Some(noi), Some(target)) if T::TargetRestriction::noi_rejects(&noi.0, source) => {
+            Err(RelationError::TargetFull(target))
         }
-
-        self.get_mut::<T>().unwrap()
+        _ => Ok(()),
     }
 }
 
+/// Describes the edges that were overwritten by a successful call to `try_insert_relation`
+#[derive(Debug)]
+pub struct InsertOutcome<T: RelKind> {
+    /// Data of the `source -> target` edge if it already existed
+    pub replaced: Option<T>,
+    /// Edge `source -> old_target` removed to uphold [`RelKind::SourceRestriction`]
+    pub evicted_target: Option<(T::Target, T)>,
+    /// Edge `old_source -> target` removed to uphold [`RelKind::TargetRestriction`]
+    pub evicted_source: Option<(Entity, T)>,
+}
 pub trait EntityRefExt {
     fn get_all_relations<T: RelKind>(&self) -> Option<RelationRefItem<'_, T>>;
-    fn get_relation<T: RelKind>(&self, target: Entity) -> Option<&T> {
-        self.get_all_relations().and_then(|item| {
-            item.into_iter().find_map(|(cur_target, data)| {
-                if cur_target == target {
-                    Some(data)
-                } else {
-                    None
-                }
-            })
-        })
+    fn get_relation<T: RelKind>(&self, target: T::Target) -> Option<&T> {
+        let rel = self.get_all_relations::<T>()?.inner;
+        T::SourceRestriction::rel_get(&rel.0, &target)
     }
     fn get_all_noitalers<T: RelKind>(&self) -> Option<NoitalerRefItem<'_, T>>;
     fn get_noitaler<T: RelKind>(&self, target: Entity) -> Option<()> {
-        self.get_all_noitalers::<T>().and_then(|item| {
-            item.into_iter()
-                .any(|cur_target| cur_target == target)
-                .then_some(())
-        })
+        let noi = self.get_all_noitalers::<T>()?.inner;
+        T::TargetRestriction::noi_contains(&noi.0, target).then_some(())
     }
 }
 pub trait EntityMutExt {
     fn get_all_relations_mut<T: RelKind>(&mut self) -> Option<RelationMutItem<'_, T>>;
-    fn get_relation_mut<T: RelKind>(&mut self, target: Entity) -> Option<&mut T> {
-        self.get_all_relations_mut().and_then(|item| {
-            item.into_iter().find_map(|(cur_target, data)| {
-                if cur_target == target {
-                    Some(data)
-                } else {
-                    None
-                }
-            })
-        })
+    fn get_relation_mut<T: RelKind>(&mut self, target: T::Target) -> Option<&mut T> {
+        let rel = self.get_all_relations_mut::<T>()?.inner.into_inner();
+        T::SourceRestriction::rel_get_mut(&mut rel.0, &target)
     }
 
     // FIXME it'd be nice if relation insert/removes could just be bundles and use "normal" apis.
     // unfortuantly bevy's `Bundle` is good for little more than "set of component types" so it is
     // not useful for us...
-    fn insert_relation<T: RelKind>(&mut self, data: T, target: Entity) -> &mut Self;
-    fn remove_relation<T: RelKind>(&mut self, target: Entity) -> &mut Self;
+    fn insert_relation<T: RelKind>(&mut self, data: T, target: T::Target) -> &mut Self;
+    /// Like `insert_relation` but returns an error instead of panicking, on error the world is
+    /// left untouched.
+    fn try_insert_relation<T: RelKind>(
+        &mut self,
+        data: T,
+        target: T::Target,
+    ) -> Result<InsertOutcome<T>, RelationError>;
+    fn remove_relation<T: RelKind>(&mut self, target: T::Target) -> &mut Self;
+    /// Removes every relation of kind `T` from this entity
+    fn clear_relations<T: RelKind>(&mut self) -> &mut Self;
+    /// Removes every relation of kind `T` that targets this entity
+    fn clear_noitalers<T: RelKind>(&mut self) -> &mut Self;
+
+    /// Like `try_insert_relation` but also moves the edge to `index` in this entity's targets,
+    /// see [`EntityMutExt::move_relation`]. `index` is clamped to the last position instead of
+    /// panicking, on error the world is left untouched.
+    fn insert_relation_at<T: RelKind>(
+        &mut self,
+        index: usize,
+        data: T,
+        target: T::Target,
+    ) -> Result<InsertOutcome<T>, RelationError>
+    where
+        T::SourceRestriction: OrderedRestriction<T>;
+    /// Moves the relation to `target` so that it ends up at `index` in this entity's targets,
+    /// shifting the targets in between. Does nothing if there is no such relation.
+    ///
+    /// Panics if `index` is out of bounds.
+    fn move_relation<T: RelKind>(&mut self, target: T::Target, index: usize) -> &mut Self
+    where
+        T::SourceRestriction: OrderedRestriction<T>;
+    /// Stable sort of this entity's targets by their targets and data, edges are only marked as
+    /// changed if any of them moved
+    fn sort_relations_by<T: RelKind, F>(&mut self, compare: F) -> &mut Self
+    where
+        T::SourceRestriction: OrderedRestriction<T>,
+        F: FnMut((&T::Target, &T), (&T::Target, &T)) -> Ordering;
+
+    /// Tries to insert the relation `source -> this entity` and moves it to `index` in this
+    /// entity's sources, see [`EntityMutExt::insert_relation_at`]. Fails with
+    /// [`RelationError::DeadSource`] if `source` is dead.
+    fn insert_noitaler_at<T: RelKind<Target = Entity>>(
+        &mut self,
+        index: usize,
+        source: Entity,
+        data: T,
+    ) -> Result<InsertOutcome<T>, RelationError>
+    where
+        T::TargetRestriction: OrderedRestriction<T>;
+    /// Moves the relation from `source` so that it ends up at `index` in this entity's sources,
+    /// shifting the sources in between. Does nothing if there is no such relation.
+    ///
+    /// Panics if `index` is out of bounds.
+    fn move_noitaler<T: RelKind>(&mut self, source: Entity, index: usize) -> &mut Self
+    where
+        T::TargetRestriction: OrderedRestriction<T>;
+    /// Stable sort of this entity's sources, see [`EntityMutExt::sort_relations_by`]
+    fn sort_noitalers_by<T: RelKind, F>(&mut self, compare: F) -> &mut Self
+    where
+        T::TargetRestriction: OrderedRestriction<T>,
+        F: FnMut(Entity, Entity) -> Ordering;
 }
 impl EntityRefExt for EntityRef<'_> {
     fn get_all_relations<T: RelKind>(&self) -> Option<RelationRefItem<'_, T>> {
@@ -165,84 +472,317 @@ impl EntityMutExt for EntityMut<'_> {
         })
     }
 
-    fn insert_relation<T: RelKind>(&mut self, data: T, target_id: Entity) -> &mut Self {
+    fn insert_relation<T: RelKind>(&mut self, data: T, target_id: T::Target) -> &mut Self {
         let source_id = self.id();
+        let mut result = Ok(());
         self.world_scope(|world| {
-            let mut source = world.entity_mut(source_id);
-            let opt_remove_target = match source.get_mut::<Relation<T>>() {
-                None => {
-                    source.insert(Relation::<T>(T::SourceRestriction::make_rel_storage(
-                        data, target_id,
-                    )));
-                    None
-                }
-                Some(mut rel) => T::SourceRestriction::push_rel(&mut rel.0, data, target_id),
-            };
-
-            if let Some(remove_target) = opt_remove_target {
-                let mut remove_target = world.entity_mut(remove_target);
-                let mut noi = remove_target.get_mut::<Noitaler<T>>().unwrap();
-                if T::TargetRestriction::remove_noi(&mut noi.0, source_id) {
-                    remove_target.remove::<Noitaler<T>>();
-                }
-            }
+            result = insert_relation(world, source_id, data, target_id.clone());
+        });
+        if let Err(e) = result {
+            panic!(
+                "Attempting to insert relation `{:?}` -> {} -> `{:?}` failed: {}",
+                self.id(),
+                std::any::type_name::<T>(),
+                target_id,
+                e,
+            );
+        }
+        self
+    }
 
-            let mut target = world.entity_mut(target_id);
-            let opt_remove_source = match target.get_mut::<Noitaler<T>>() {
-                None => {
-                    target.insert(Noitaler::<T>(T::TargetRestriction::make_noi_storage(
-                        source_id,
-                    )));
-                    None
-                }
-                Some(mut noi) => T::TargetRestriction::push_noi(&mut noi.0, source_id),
-            };
-
-            if let Some(remove_source) = opt_remove_source {
-                let mut remove_source = world.entity_mut(remove_source);
-                let mut rel = remove_source.get_mut::<Relation<T>>().unwrap();
-                if T::SourceRestriction::remove_rel(&mut rel.0, target_id) {
-                    remove_source.remove::<Relation<T>>();
-                }
+    fn try_insert_relation<T: RelKind>(
+        &mut self,
+        data: T,
+        target_id: T::Target,
+    ) -> Result<InsertOutcome<T>, RelationError> {
+        let source_id = self.id();
+        let mut result = None;
+        self.world_scope(|world| {
+            let outcome = try_insert_relation(world, source_id, data, target_id.clone());
+            if let Ok(outcome) = &outcome {
+                send_insert_events(world, source_id, target_id, || {
+                    outcome.events_without_data()
+                });
             }
+            result = Some(outcome);
+        });
+        result.unwrap()
+    }
 
-            if let Err(()) = T::Cyclicity::assert_cyclicity(world.entity_mut(source_id)) {
-                panic!(
-                    "Attempting to insert relation `{:?}` -> {} -> `{:?}` introduces a cycle.",
-                    source_id,
-                    std::any::type_name::<T>(),
-                    target_id
+    fn remove_relation<T: RelKind>(&mut self, remove_target: T::Target) -> &mut Self {
+        let source_id = self.id();
+
+        self.world_scope(|w| {
+            if let Some(data) = remove_edge::<T>(w, source_id, &remove_target) {
+                let mirror = remove_mirror::<T>(w, source_id, &remove_target);
+                send_removed_events(
+                    w,
+                    std::iter::once((source_id, remove_target, Some(data))).chain(mirror),
                 );
             }
         });
+
         self
     }
 
-    fn remove_relation<T: RelKind>(&mut self, remove_target: Entity) -> &mut Self {
+    fn clear_relations<T: RelKind>(&mut self) -> &mut Self {
         let source_id = self.id();
+        self.world_scope(|world| {
+            clear_relations_with_events::<T>(world, source_id);
+            if T::Symmetry::SYMMETRIC {
+                clear_noitalers_with_events::<T>(world, source_id);
+            }
+        });
+        self
+    }
 
-        self.world_scope(|w| {
-            let mut source = w.entity_mut(source_id);
-            if let Some(mut source_rel) = source.get_mut::<Relation<T>>() {
-                if T::SourceRestriction::remove_rel(&mut source_rel.0, remove_target) {
-                    source.remove::<Relation<T>>();
-                }
+    fn clear_noitalers<T: RelKind>(&mut self) -> &mut Self {
+        let target_id = self.id();
+        self.world_scope(|world| {
+            clear_noitalers_with_events::<T>(world, target_id);
+            if T::Symmetry::SYMMETRIC {
+                clear_relations_with_events::<T>(world, target_id);
             }
+        });
+        self
+    }
 
-            let mut target = w.entity_mut(remove_target);
-            if let Some(mut target_rel) = target.get_mut::<Noitaler<T>>() {
-                if T::TargetRestriction::remove_noi(&mut target_rel.0, source_id) {
-                    target.remove::<Noitaler<T>>();
-                }
+    fn insert_relation_at<T: RelKind>(
+        &mut self,
+        index: usize,
+        data: T,
+        target: T::Target,
+    ) -> Result<InsertOutcome<T>, RelationError>
+    where
+        T::SourceRestriction: OrderedRestriction<T>,
+    {
+        let outcome = self.try_insert_relation(data, target.clone())?;
+        let rel = &self.get::<Relation<T>>().unwrap().0;
+        let last = T::SourceRestriction::rel_iter(rel).1.count() - 1;
+        self.move_relation::<T>(target, index.min(last));
+        Ok(outcome)
+    }
+
+    // reordering is a change to the set of edges rather than to their data
+    fn move_relation<T: RelKind>(&mut self, target: T::Target, index: usize) -> &mut Self
+    where
+        T::SourceRestriction: OrderedRestriction<T>,
+    {
+        let moved = match self.get_mut::<Relation<T>>() {
+            Some(mut rel) => {
+                T::SourceRestriction::rel_move(&mut rel.bypass_change_detection().0, &target, index)
+            }
+            None => false,
+        };
+        if moved {
+            self.get_mut::<RelationEdges<T>>().unwrap().set_changed();
+        }
+        self
+    }
+
+    fn sort_relations_by<T: RelKind, F>(&mut self, compare: F) -> &mut Self
+    where
+        T::SourceRestriction: OrderedRestriction<T>,
+        F: FnMut((&T::Target, &T), (&T::Target, &T)) -> Ordering,
+    {
+        if let Some(mut rel) = self.get_mut::<Relation<T>>() {
+            if T::SourceRestriction::rel_sort_by(&mut rel.bypass_change_detection().0, compare) {
+                self.get_mut::<RelationEdges<T>>().unwrap().set_changed();
+            }
+        }
+        self
+    }
+
+    fn insert_noitaler_at<T: RelKind<Target = Entity>>(
+        &mut self,
+        index: usize,
+        source: Entity,
+        data: T,
+    ) -> Result<InsertOutcome<T>, RelationError>
+    where
+        T::TargetRestriction: OrderedRestriction<T>,
+    {
+        let target = self.id();
+        let mut result = Err(RelationError::DeadSource(source));
+        self.world_scope(|world| {
+            if let Some(mut source) = world.get_entity_mut(source) {
+                result = source.try_insert_relation(data, target);
             }
         });
+        let outcome = result?;
+        let noi = &self.get::<Noitaler<T>>().unwrap().0;
+        let last = T::TargetRestriction::noi_iter(noi).count() - 1;
+        self.move_noitaler::<T>(source, index.min(last));
+        Ok(outcome)
+    }
 
+    fn move_noitaler<T: RelKind>(&mut self, source: Entity, index: usize) -> &mut Self
+    where
+        T::TargetRestriction: OrderedRestriction<T>,
+    {
+        if let Some(mut noi) = self.get_mut::<Noitaler<T>>() {
+            if T::TargetRestriction::noi_move(&mut noi.bypass_change_detection().0, source, index) {
+                noi.set_changed();
+            }
+        }
+        self
+    }
+
+    fn sort_noitalers_by<T: RelKind, F>(&mut self, compare: F) -> &mut Self
+    where
+        T::TargetRestriction: OrderedRestriction<T>,
+        F: FnMut(Entity, Entity) -> Ordering,
+    {
+        if let Some(mut noi) = self.get_mut::<Noitaler<T>>() {
+            if T::TargetRestriction::noi_sort_by(&mut noi.bypass_change_detection().0, compare) {
+                noi.set_changed();
+            }
+        }
         self
     }
 }
 
+/// Inserts a relation for callers that have no use for the [`InsertOutcome`] so that the
+/// overwritten data can be sent with the relation events instead
+fn insert_relation<T: RelKind>(
+    world: &mut World,
+    source_id: Entity,
+    data: T,
+    target_id: T::Target,
+) -> Result<(), RelationError> {
+    let outcome = try_insert_relation(world, source_id, data, target_id.clone())?;
+    send_insert_events(world, source_id, target_id, || outcome.into_events());
+    Ok(())
+}
+
+/// Does not send any relation events, that is left to the caller
+fn try_insert_relation<T: RelKind>(
+    world: &mut World,
+    source_id: Entity,
+    data: T,
+    target_id: T::Target,
+) -> Result<InsertOutcome<T>, RelationError> {
+    let mirror = T::Symmetry::mirror(&data);
+    let outcome = try_insert_edge(world, source_id, data, target_id.clone())?;
+
+    if let Some(mirror) = mirror {
+        if let Some((old_target, _)) = &outcome.evicted_target {
+            remove_mirror::<T>(world, source_id, old_target);
+        }
+        if let Some((old_source, _)) = &outcome.evicted_source {
+            remove_mirror::<T>(world, *old_source, &target_id);
+        }
+        // symmetric kinds always target entities
+        let target_entity = target_id.as_entity().unwrap();
+        if target_entity != source_id {
+            // the evictions above made room for the mirror on both entities so this can only
+            // replace the data of an existing mirror
+            let source = T::Target::from_entity(source_id).unwrap();
+            try_insert_edge(world, target_entity, mirror, source).unwrap();
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// Inserts `source -> target` without mirroring it for [`symmetry::Symmetric`] kinds
+fn try_insert_edge<T: RelKind>(
+    world: &mut World,
+    source_id: Entity,
+    data: T,
+    target_id: T::Target,
+) -> Result<InsertOutcome<T>, RelationError> {
+    if let Some(target_entity) = target_id.as_entity() {
+        if world.get_entity(target_entity).is_none() {
+            return Err(RelationError::DeadTarget(target_entity));
+        }
+
+        // Checked before touching the world so that there is nothing to roll back on error.
+        // Evictions can only remove edges out of `source` or into `target` which can never be
+        // part of a `target -> .. -> source` path so this is the same as checking afterwards.
+        if let Some(path) = T::Cyclicity::find_cycle(&*world, source_id, target_entity) {
+            return Err(match path.len() {
+                1 => RelationError::SelfEdge(source_id),
+                _ => RelationError::Cycle(path),
+            });
+        }
+    }
+    check_rejected::<T>(
+        world.get::<Relation<T>>(source_id),
+        target_id
+            .as_entity()
+            .and_then(|target| world.get::<Noitaler<T>>(target)),
+        source_id,
+        &target_id,
+    )?;
+
+    let mut outcome = InsertOutcome {
+        replaced: None,
+        evicted_target: None,
+        evicted_source: None,
+    };
+
+    let mut source = world.entity_mut(source_id);
+    let opt_overwritten = match source.get_mut::<Relation<T>>() {
+        None => {
+            source.insert((
+                Relation::<T>(T::SourceRestriction::make_rel_storage(
+                    data,
+                    target_id.clone(),
+                )),
+                RelationEdges::<T>(PhantomData),
+            ));
+            None
+        }
+        Some(mut rel) => T::SourceRestriction::push_rel(&mut rel.0, data, target_id.clone()),
+    };
+
+    match opt_overwritten {
+        Some((old_target, old_data)) if old_target == target_id => {
+            // the edge already existed so there is nothing to do for `Noitaler<T>`
+            outcome.replaced = Some(old_data);
+            return Ok(outcome);
+        }
+        Some((remove_target_id, old_data)) => {
+            if let Some(remove_target_id) = remove_target_id.as_entity() {
+                remove_noi_entry::<T>(world, remove_target_id, source_id);
+            }
+            outcome.evicted_target = Some((remove_target_id, old_data));
+        }
+        None => (),
+    }
+    world
+        .entity_mut(source_id)
+        .get_mut::<RelationEdges<T>>()
+        .unwrap()
+        .set_changed();
+
+    let target_entity = match target_id.as_entity() {
+        Some(target_entity) => target_entity,
+        None => return Ok(outcome),
+    };
+    let mut target = world.entity_mut(target_entity);
+    let opt_remove_source = match target.get_mut::<Noitaler<T>>() {
+        None => {
+            target.insert(Noitaler::<T>(T::TargetRestriction::make_noi_storage(
+                source_id,
+            )));
+            None
+        }
+        Some(mut noi) => T::TargetRestriction::push_noi(&mut noi.0, source_id),
+    };
+
+    if let Some(remove_source_id) = opt_remove_source {
+        let old_data = remove_rel_entry(world, remove_source_id, &target_id).unwrap();
+        outcome.evicted_source = Some((remove_source_id, old_data));
+    }
+
+    Ok(outcome)
+}
+
 pub mod commands {
     use bevy::ecs::system::EntityCommands;
+    use bevy::log::warn;
 
     use super::{Command, Entity, EntityMutExt, RelKind, World};
     use std::marker::PhantomData;
@@ -251,19 +791,31 @@ pub mod commands {
         fn insert_relation<T: RelKind>(
             &mut self,
             data: T,
-            target: Entity,
+            target: T::Target,
+        ) -> &mut EntityCommands<'w, 's, 'a>;
+
+        /// Like `insert_relation` but logs a warning instead of panicking if the relation
+        /// could not be inserted.
+        fn try_insert_relation<T: RelKind>(
+            &mut self,
+            data: T,
+            target: T::Target,
         ) -> &mut EntityCommands<'w, 's, 'a>;
 
         fn remove_relation<T: RelKind>(
             &mut self,
-            target: Entity,
+            target: T::Target,
         ) -> &mut EntityCommands<'w, 's, 'a>;
+
+        fn clear_relations<T: RelKind>(&mut self) -> &mut EntityCommands<'w, 's, 'a>;
+
+        fn clear_noitalers<T: RelKind>(&mut self) -> &mut EntityCommands<'w, 's, 'a>;
     }
 
     pub struct InsertRelation<T: RelKind> {
         source: Entity,
         data: T,
-        target: Entity,
+        target: T::Target,
     }
     impl<T: RelKind> Command for InsertRelation<T> {
         fn write(self, world: &mut World) {
@@ -273,16 +825,70 @@ pub mod commands {
         }
     }
 
+    pub struct TryInsertRelation<T: RelKind> {
+        source: Entity,
+        data: T,
+        target: T::Target,
+    }
+    impl<T: RelKind> Command for TryInsertRelation<T> {
+        fn write(self, world: &mut World) {
+            if world.get_entity(self.source).is_none() {
+                warn!(
+                    "Could not insert relation `{:?}` -> {} -> `{:?}`: source does not exist",
+                    self.source,
+                    std::any::type_name::<T>(),
+                    self.target,
+                );
+                return;
+            }
+            let target = self.target.clone();
+            if let Err(e) = super::insert_relation(world, self.source, self.data, target) {
+                warn!(
+                    "Could not insert relation `{:?}` -> {} -> `{:?}`: {}",
+                    self.source,
+                    std::any::type_name::<T>(),
+                    self.target,
+                    e,
+                );
+            }
+        }
+    }
+
     pub struct RemoveRelation<T: RelKind> {
         source: Entity,
-        target: Entity,
+        target: T::Target,
         _p: PhantomData<T>,
     }
     impl<T: RelKind> Command for RemoveRelation<T> {
         fn write(self, world: &mut World) {
-            world
-                .entity_mut(self.source)
-                .remove_relation::<T>(self.target);
+            // a despawned source has already had its relations removed
+            if let Some(mut source) = world.get_entity_mut(self.source) {
+                source.remove_relation::<T>(self.target);
+            }
+        }
+    }
+
+    pub struct ClearRelations<T: RelKind> {
+        source: Entity,
+        _p: PhantomData<T>,
+    }
+    impl<T: RelKind> Command for ClearRelations<T> {
+        fn write(self, world: &mut World) {
+            if let Some(mut source) = world.get_entity_mut(self.source) {
+                source.clear_relations::<T>();
+            }
+        }
+    }
+
+    pub struct ClearNoitalers<T: RelKind> {
+        target: Entity,
+        _p: PhantomData<T>,
+    }
+    impl<T: RelKind> Command for ClearNoitalers<T> {
+        fn write(self, world: &mut World) {
+            if let Some(mut target) = world.get_entity_mut(self.target) {
+                target.clear_noitalers::<T>();
+            }
         }
     }
 
@@ -290,7 +896,7 @@ pub mod commands {
         fn insert_relation<T: RelKind>(
             &mut self,
             data: T,
-            target: Entity,
+            target: T::Target,
         ) -> &mut EntityCommands<'w, 's, 'a> {
             let source = self.id();
             self.commands().add(InsertRelation {
@@ -301,9 +907,23 @@ pub mod commands {
             self
         }
 
+        fn try_insert_relation<T: RelKind>(
+            &mut self,
+            data: T,
+            target: T::Target,
+        ) -> &mut EntityCommands<'w, 's, 'a> {
+            let source = self.id();
+            self.commands().add(TryInsertRelation {
+                source,
+                data,
+                target,
+            });
+            self
+        }
+
         fn remove_relation<T: RelKind>(
             &mut self,
-            target: Entity,
+            target: T::Target,
         ) -> &mut EntityCommands<'w, 's, 'a> {
             let source = self.id();
             self.commands().add(RemoveRelation {
@@ -313,13 +933,31 @@ pub mod commands {
             });
             self
         }
+
+        fn clear_relations<T: RelKind>(&mut self) -> &mut EntityCommands<'w, 's, 'a> {
+            let source = self.id();
+            self.commands().add(ClearRelations {
+                source,
+                _p: PhantomData::<T>,
+            });
+            self
+        }
+
+        fn clear_noitalers<T: RelKind>(&mut self) -> &mut EntityCommands<'w, 's, 'a> {
+            let target = self.id();
+            self.commands().add(ClearNoitalers {
+                target,
+                _p: PhantomData::<T>,
+            });
+            self
+        }
     }
 }
 
 mod world_queries {
-    use crate::{Noitaler, RelKind, Relation};
+    use crate::{Noitaler, RelKind, Relation, RelationEdges};
     use bevy::ecs::query::WorldQuery;
-    use bevy::prelude::{With, Without};
+    use bevy::prelude::{Added, Changed, With, Without};
 
     // necessary for `derive(WorldQuery)` this is fixed in `0.10`
     use bevy::ecs::entity::Entity;
@@ -330,26 +968,80 @@ mod world_queries {
         pub(crate) inner: &'static mut Relation<T>,
     }
 
+    /// Used by [`RelationsMut`](crate::RelationsMut) which needs to mark edges as changed
+    #[doc(hidden)]
+    #[derive(WorldQuery)]
+    #[world_query(mutable)]
+    pub struct RelationEdgesMut<T: RelKind> {
+        pub(crate) inner: &'static mut Relation<T>,
+        pub(crate) edges: &'static mut RelationEdges<T>,
+    }
+
+    #[doc(hidden)]
+    #[derive(WorldQuery)]
+    #[world_query(mutable)]
+    pub struct NoitalerMut<T: RelKind> {
+        pub(crate) inner: &'static mut Noitaler<T>,
+    }
+
     #[derive(WorldQuery)]
     pub struct NoitalerRef<T: RelKind> {
         pub(crate) inner: &'static Noitaler<T>,
     }
 
+    /// Filters for entities with a storage for relations of kind `R`, storages emptied by
+    /// [`RelationsMut`](crate::RelationsMut) still match until commands are applied
     #[derive(WorldQuery)]
     pub struct WithRelation<R: RelKind> {
         inner: With<Relation<R>>,
     }
 
+    /// Opposite of [`WithRelation`]
     #[derive(WorldQuery)]
     pub struct WithoutRelation<R: RelKind> {
         inner: Without<Relation<R>>,
     }
+
+    /// Filters for entities that had their first relation of kind `R` inserted
+    #[derive(WorldQuery)]
+    pub struct AddedRelation<R: RelKind> {
+        inner: Added<Relation<R>>,
+    }
+
+    /// Filters for entities that had a relation of kind `R` inserted or removed. Entities that
+    /// had their last relation removed do not match this filter.
+    #[derive(WorldQuery)]
+    pub struct ChangedRelation<R: RelKind> {
+        inner: Changed<RelationEdges<R>>,
+    }
+
+    /// Filters for entities that had the data of a relation of kind `R` mutably accessed,
+    /// inserting a relation also counts as changing its data.
+    #[derive(WorldQuery)]
+    pub struct ChangedRelationData<R: RelKind> {
+        inner: Changed<Relation<R>>,
+    }
+
+    /// Filters for entities that had a source of kind `R` start or stop targetting them
+    #[derive(WorldQuery)]
+    pub struct ChangedNoitaler<R: RelKind> {
+        inner: Changed<Noitaler<R>>,
+    }
 }
 
 mod sealed {
     pub trait Sealed {}
-    impl Sealed for super::restriction::Many {}
-    impl Sealed for super::restriction::One {}
+    impl Sealed for super::restriction::Unordered {}
+    impl Sealed for super::restriction::Ordered {}
+    impl Sealed for super::restriction::EvictOldest {}
+    impl Sealed for super::restriction::EvictNewest {}
+    impl Sealed for super::restriction::Reject {}
     impl Sealed for super::cyclicity::Cyclic {}
     impl Sealed for super::cyclicity::Acyclic {}
+    impl Sealed for super::despawn_policy::Recursive {}
+    impl Sealed for super::despawn_policy::Detach {}
+    impl Sealed for super::despawn_policy::Deny {}
+    impl Sealed for super::despawn_policy::Retarget {}
+    impl Sealed for super::symmetry::Directed {}
+    impl Sealed for super::symmetry::Symmetric {}
 }
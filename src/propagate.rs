@@ -0,0 +1,127 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    app::{App, CoreStage, Plugin},
+    ecs::prelude::*,
+    utils::HashSet,
+};
+
+use crate::{
+    cyclicity::Acyclic, restriction::One, RelKind, RelationEdges, Relations, WithRelation,
+    WithoutRelation,
+};
+
+/// Components that are combined with the value of their target to compute [`Propagate::Out`],
+/// e.g. `Transform` computing `GlobalTransform`. See [`PropagatePlugin`].
+pub trait Propagate: Component {
+    type Out: Component + Clone;
+
+    /// Computes the value for entities without a target
+    fn root(local: &Self) -> Self::Out;
+    /// Computes the value for an entity from the value of its target
+    fn propagate(parent: &Self::Out, local: &Self) -> Self::Out;
+}
+
+/// Updates [`Propagate::Out`] from `C` along relations of kind `R` in [`CoreStage::PostUpdate`].
+/// Only entities with both `C` and `C::Out` are updated, propagation stops at entities without them.
+///
+/// Only the subtrees below entities whose `C` changed, or that got a new target or lost their
+/// target, are visited so unchanged parts of the graph cost nothing beyond change detection.
+pub struct PropagatePlugin<R, C>(PhantomData<fn() -> (R, C)>);
+impl<R, C> Default for PropagatePlugin<R, C> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<R, C> Plugin for PropagatePlugin<R, C>
+where
+    R: RelKind<Target = Entity, SourceRestriction = One, Cyclicity = Acyclic>,
+    C: Propagate,
+{
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PostUpdate, propagate_system::<R, C>);
+    }
+}
+
+type Roots<'w, 's, R, C> =
+    Query<'w, 's, (&'static C, &'static mut <C as Propagate>::Out), WithoutRelation<R>>;
+type Nodes<'w, 's, R, C> =
+    Query<'w, 's, (&'static C, &'static mut <C as Propagate>::Out), WithRelation<R>>;
+type Dirty<'w, 's, R, C> = Query<
+    'w,
+    's,
+    Entity,
+    (
+        With<C>,
+        With<<C as Propagate>::Out>,
+        Or<(Changed<C>, Changed<RelationEdges<R>>)>,
+    ),
+>;
+
+fn propagate_system<R, C>(
+    mut roots: Roots<R, C>,
+    mut nodes: Nodes<R, C>,
+    relations: Relations<R>,
+    changed: Dirty<R, C>,
+    detached: RemovedComponents<RelationEdges<R>>,
+) where
+    R: RelKind<Target = Entity, SourceRestriction = One, Cyclicity = Acyclic>,
+    C: Propagate,
+{
+    let dirty = changed
+        .iter()
+        .chain(detached.iter().filter(|&entity| roots.contains(entity)))
+        .collect::<HashSet<_>>();
+
+    for &entity in &dirty {
+        // subtrees of dirty entities are updated starting from the dirty entity closest to the
+        // root, entities below an entity without `C` or `C::Out` are never updated
+        if relations.ancestors(entity).any(|ancestor| {
+            dirty.contains(&ancestor) || !(roots.contains(ancestor) || nodes.contains(ancestor))
+        }) {
+            continue;
+        }
+
+        let out = match roots.get_mut(entity) {
+            Ok((local, mut out)) => {
+                *out = C::root(local);
+                out.clone()
+            }
+            Err(_) => {
+                // storages emptied by `RelationsMut` are still around until commands are applied
+                let parent = match relations.targets(entity).next() {
+                    Some(parent) => parent,
+                    None => continue,
+                };
+                let parent_out = match roots.get(parent) {
+                    Ok((_, out)) => out.clone(),
+                    Err(_) => nodes.get(parent).unwrap().1.clone(),
+                };
+                let (local, mut out) = nodes.get_mut(entity).unwrap();
+                *out = C::propagate(&parent_out, local);
+                out.clone()
+            }
+        };
+
+        // explicit stack so deep chains cannot overflow the call stack
+        let mut stack = relations
+            .sources(entity)
+            .map(|source| (source, out.clone()))
+            .collect::<Vec<_>>();
+        while let Some((entity, parent)) = stack.pop() {
+            let out = match nodes.get_mut(entity) {
+                Ok((local, mut out)) => {
+                    *out = C::propagate(&parent, local);
+                    out.clone()
+                }
+                Err(_) => continue,
+            };
+            stack.extend(
+                relations
+                    .sources(entity)
+                    .map(|source| (source, out.clone())),
+            );
+        }
+    }
+}
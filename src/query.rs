@@ -0,0 +1,114 @@
+use bevy::ecs::{
+    prelude::*,
+    query::{QueryItem, ROQueryItem, ReadOnlyWorldQuery, WorldQuery},
+    system::SystemParam,
+};
+
+use crate::{NoitalerRef, RelKind, RelationRef, WithRelation};
+
+/// A [`Query`] for the sources of relations of kind `T` that point to some target given at
+/// runtime. Sources are looked up from the target so this does not scan every source.
+///
+/// ```ignore
+/// fn system(units: RelatesTo<InGroup, &mut Transform>, groups: Query<Entity, With<Group>>) {
+///     for group in &groups {
+///         units.for_each_mut(group, |transform| { .. });
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct RelatesTo<'w, 's, T, Q, F = ()>
+where
+    T: RelKind<Target = Entity>,
+    Q: WorldQuery + 'static,
+    F: ReadOnlyWorldQuery + 'static,
+{
+    query: Query<'w, 's, Q, (F, WithRelation<T>)>,
+    noitalers: Query<'w, 's, NoitalerRef<T>>,
+}
+impl<'w, 's, T, Q, F> RelatesTo<'w, 's, T, Q, F>
+where
+    T: RelKind<Target = Entity>,
+    Q: WorldQuery + 'static,
+    F: ReadOnlyWorldQuery + 'static,
+{
+    /// Returns the query items of every source with a relation to `target`
+    pub fn iter(&self, target: Entity) -> impl Iterator<Item = ROQueryItem<'_, Q>> + '_ {
+        self.query.iter_many(self.iter_sources(target))
+    }
+
+    /// Like [`RelatesTo::iter`] but calls `f` with mutable query items
+    pub fn for_each_mut(&mut self, target: Entity, mut f: impl FnMut(QueryItem<'_, Q>)) {
+        let sources = self.noitalers.get(target).ok().into_iter().flatten();
+        let mut iter = self.query.iter_many_mut(sources);
+        while let Some(item) = iter.fetch_next() {
+            f(item);
+        }
+    }
+
+    /// Returns whether `source` has a relation to `target` and matches the query
+    pub fn contains(&self, source: Entity, target: Entity) -> bool {
+        self.iter_sources(target)
+            .any(|cur_source| cur_source == source)
+            && self.query.contains(source)
+    }
+
+    fn iter_sources(&self, target: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.noitalers.get(target).ok().into_iter().flatten()
+    }
+}
+
+/// A [`Query`] for the targets of relations of kind `T` from some source given at runtime, the
+/// [`RelatesTo`] equivalent for the target side.
+#[derive(SystemParam)]
+pub struct TargetedBy<'w, 's, T, Q, F = ()>
+where
+    T: RelKind<Target = Entity>,
+    Q: WorldQuery + 'static,
+    F: ReadOnlyWorldQuery + 'static,
+{
+    query: Query<'w, 's, Q, F>,
+    relations: Query<'w, 's, RelationRef<T>>,
+}
+impl<'w, 's, T, Q, F> TargetedBy<'w, 's, T, Q, F>
+where
+    T: RelKind<Target = Entity>,
+    Q: WorldQuery + 'static,
+    F: ReadOnlyWorldQuery + 'static,
+{
+    /// Returns the query items of every target of `source`
+    pub fn iter(&self, source: Entity) -> impl Iterator<Item = ROQueryItem<'_, Q>> + '_ {
+        self.query.iter_many(self.iter_targets(source))
+    }
+
+    /// Like [`TargetedBy::iter`] but calls `f` with mutable query items
+    pub fn for_each_mut(&mut self, source: Entity, mut f: impl FnMut(QueryItem<'_, Q>)) {
+        let targets = self
+            .relations
+            .get(source)
+            .ok()
+            .into_iter()
+            .flatten()
+            .map(|(target, _)| target);
+        let mut iter = self.query.iter_many_mut(targets);
+        while let Some(item) = iter.fetch_next() {
+            f(item);
+        }
+    }
+
+    /// Returns whether `target` is the target of a relation from `source` and matches the query
+    pub fn contains(&self, target: Entity, source: Entity) -> bool {
+        self.iter_targets(source)
+            .any(|cur_target| cur_target == target)
+            && self.query.contains(target)
+    }
+
+    fn iter_targets(&self, source: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.relations
+            .get(source)
+            .ok()
+            .into_iter()
+            .flatten()
+            .map(|(target, _)| target)
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::{
+    ecs::prelude::*,
+    reflect::{FromReflect, FromType, Reflect, TypeRegistration, TypeRegistryInternal},
+};
+
+use crate::{EntityMutExt, Noitaler, RelKind, Relation, RelationError, Restriction};
+
+type InsertFn = fn(&mut World, Entity, &dyn Reflect, &dyn Reflect) -> Result<(), RelationError>;
+
+/// Type data for working with relations of a kind without knowing its type, e.g. from editors
+/// and inspectors. Registered for `T` by
+/// [`RelationAppExt::register_relation`](crate::app::RelationAppExt::register_relation), or
+/// through `#[reflect(Relation)]` when deriving [`Reflect`].
+///
+/// Targets are passed and returned as `dyn Reflect` since kinds do not have to target entities,
+/// passing values of the wrong type panics.
+#[derive(Clone)]
+pub struct ReflectRelation {
+    targets: fn(&World, Entity) -> Vec<Box<dyn Reflect>>,
+    sources: fn(&World, Entity) -> Vec<Entity>,
+    data: for<'a> fn(&'a World, Entity, &dyn Reflect) -> Option<&'a dyn Reflect>,
+    data_mut: for<'a> fn(&'a mut World, Entity, &dyn Reflect) -> Option<&'a mut dyn Reflect>,
+    insert: InsertFn,
+    remove: fn(&mut World, Entity, &dyn Reflect),
+}
+
+impl ReflectRelation {
+    /// Targets of the relations out of `source`
+    pub fn targets(&self, world: &World, source: Entity) -> Vec<Box<dyn Reflect>> {
+        (self.targets)(world, source)
+    }
+
+    /// Sources of the relations pointing to `target`
+    pub fn sources(&self, world: &World, target: Entity) -> Vec<Entity> {
+        (self.sources)(world, target)
+    }
+
+    /// Data of the `source -> target` relation
+    pub fn data<'a>(
+        &self,
+        world: &'a World,
+        source: Entity,
+        target: &dyn Reflect,
+    ) -> Option<&'a dyn Reflect> {
+        (self.data)(world, source, target)
+    }
+
+    /// Data of the `source -> target` relation, marks the data of every relation of this kind on
+    /// `source` as changed
+    pub fn data_mut<'a>(
+        &self,
+        world: &'a mut World,
+        source: Entity,
+        target: &dyn Reflect,
+    ) -> Option<&'a mut dyn Reflect> {
+        (self.data_mut)(world, source, target)
+    }
+
+    /// Like [`EntityMutExt::try_insert_relation`], `data` has to be the relation kind or a
+    /// dynamic value that it can be created from through [`FromReflect`]. Fails with
+    /// [`RelationError::DeadSource`] if `source` is dead
+    pub fn insert(
+        &self,
+        world: &mut World,
+        source: Entity,
+        data: &dyn Reflect,
+        target: &dyn Reflect,
+    ) -> Result<(), RelationError> {
+        (self.insert)(world, source, data, target)
+    }
+
+    /// Like [`EntityMutExt::remove_relation`], does nothing if `source` is dead
+    pub fn remove(&self, world: &mut World, source: Entity, target: &dyn Reflect) {
+        (self.remove)(world, source, target)
+    }
+}
+
+fn from_reflect<T: FromReflect>(value: &dyn Reflect) -> T {
+    T::from_reflect(value).unwrap_or_else(|| {
+        panic!(
+            "`{}` could not be created from a reflected `{}`",
+            std::any::type_name::<T>(),
+            value.type_name(),
+        )
+    })
+}
+
+impl<T> FromType<T> for ReflectRelation
+where
+    T: RelKind + FromReflect,
+    T::Target: FromReflect,
+{
+    fn from_type() -> Self {
+        ReflectRelation {
+            targets: |world, source| match world.get::<Relation<T>>(source) {
+                Some(rel) => T::SourceRestriction::rel_iter(&rel.0)
+                    .1
+                    .map(|target| Box::new(target.clone()) as Box<dyn Reflect>)
+                    .collect(),
+                None => Vec::new(),
+            },
+            sources: |world, target| match world.get::<Noitaler<T>>(target) {
+                Some(noi) => T::TargetRestriction::noi_iter(&noi.0).collect(),
+                None => Vec::new(),
+            },
+            data: |world, source, target| {
+                let rel = world.get::<Relation<T>>(source)?;
+                T::SourceRestriction::rel_get(&rel.0, &from_reflect(target))
+                    .map(|data| data as &dyn Reflect)
+            },
+            data_mut: |world, source, target| {
+                let rel = world.get_mut::<Relation<T>>(source)?.into_inner();
+                T::SourceRestriction::rel_get_mut(&mut rel.0, &from_reflect(target))
+                    .map(|data| data as &mut dyn Reflect)
+            },
+            insert: |world, source, data, target| {
+                world
+                    .get_entity_mut(source)
+                    .ok_or(RelationError::DeadSource(source))?
+                    .try_insert_relation::<T>(from_reflect(data), from_reflect(target))
+                    .map(drop)
+            },
+            remove: |world, source, target| {
+                if let Some(mut source) = world.get_entity_mut(source) {
+                    source.remove_relation::<T>(from_reflect(target));
+                }
+            },
+        }
+    }
+}
+
+/// Registrations of the relation kinds that `entity` is the source or target of, only kinds
+/// with [`ReflectRelation`] registered are found
+pub fn relation_kinds<'a>(
+    registry: &'a TypeRegistryInternal,
+    world: &World,
+    entity: Entity,
+) -> Vec<&'a TypeRegistration> {
+    registry
+        .iter()
+        .filter(|registration| {
+            registration
+                .data::<ReflectRelation>()
+                .is_some_and(|reflect| {
+                    !reflect.targets(world, entity).is_empty()
+                        || !reflect.sources(world, entity).is_empty()
+                })
+        })
+        .collect()
+}
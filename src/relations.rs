@@ -0,0 +1,376 @@
+use bevy::ecs::{entity::Entities, event::Events, prelude::*, system::SystemParam};
+
+use crate::{
+    cyclicity::AssertTreeIfAcyclic,
+    events::insert_events,
+    traverse::{self, Ancestors, Descendants, Leaves, Siblings},
+    world_queries::{NoitalerMut, RelationEdgesMut},
+    EntityCommandsExt, InsertOutcome, Noitaler, NoitalerRef, RelKind, Relation, RelationEdges,
+    RelationError, RelationEvent, RelationRef, RelationTarget, Restriction, Symmetry,
+};
+
+/// Random access to edges of kind `T` in both directions. Only reads relation components so it
+/// can run in parallel with any system that does not mutate relations of kind `T`.
+#[derive(SystemParam)]
+pub struct Relations<'w, 's, T: RelKind> {
+    relations: Query<'w, 's, RelationRef<T>>,
+    noitalers: Query<'w, 's, NoitalerRef<T>>,
+}
+impl<'w, 's, T: RelKind> Relations<'w, 's, T> {
+    /// Returns the targets of `source` along with the data of each edge
+    pub fn get(&self, source: Entity) -> impl Iterator<Item = (T::Target, &T)> + '_ {
+        self.relations.get(source).ok().into_iter().flatten()
+    }
+
+    pub fn targets(&self, source: Entity) -> impl Iterator<Item = T::Target> + '_ {
+        self.get(source).map(|(target, _)| target)
+    }
+
+    pub fn sources(&self, target: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.noitalers.get(target).ok().into_iter().flatten()
+    }
+
+    /// Returns the data of the `source -> target` edge
+    pub fn data(&self, source: Entity, target: T::Target) -> Option<&T> {
+        let rel = self.relations.get(source).ok()?.inner;
+        T::SourceRestriction::rel_get(&rel.0, &target)
+    }
+
+    /// Returns whether the `source -> target` edge exists
+    pub fn has(&self, source: Entity, target: T::Target) -> bool {
+        self.data(source, target).is_some()
+    }
+}
+impl<'w, 's, T: RelKind<Target = Entity>> Relations<'w, 's, T> {
+    /// See [`Traverse::ancestors`](crate::Traverse::ancestors)
+    pub fn ancestors(&self, entity: Entity) -> Ancestors<T, &Self> {
+        traverse::ancestors(self, entity)
+    }
+    /// See [`Traverse::descendants_bfs`](crate::Traverse::descendants_bfs)
+    pub fn descendants_bfs(&self, entity: Entity) -> Descendants<T, &Self> {
+        traverse::descendants_bfs(self, entity)
+    }
+    /// See [`Traverse::descendants_dfs`](crate::Traverse::descendants_dfs)
+    pub fn descendants_dfs(&self, entity: Entity) -> Descendants<T, &Self> {
+        traverse::descendants_dfs(self, entity)
+    }
+    /// See [`Traverse::root`](crate::Traverse::root)
+    pub fn root(&self, entity: Entity) -> Entity {
+        traverse::root::<T, _>(self, entity)
+    }
+    /// See [`Traverse::leaves`](crate::Traverse::leaves)
+    pub fn leaves(&self, entity: Entity) -> Leaves<T, &Self> {
+        traverse::leaves(self, entity)
+    }
+    /// See [`Traverse::siblings`](crate::Traverse::siblings)
+    pub fn siblings(&self, entity: Entity) -> Siblings {
+        traverse::siblings::<T, _>(self, entity)
+    }
+}
+
+/// Whether an insert made through [`RelationsMut`] could be applied immediately
+#[derive(Debug)]
+pub enum Applied<T: RelKind> {
+    /// The edge is visible to the rest of the system, see [`InsertOutcome`]
+    Immediately(InsertOutcome<T>),
+    /// The source or target had no edges of kind `T` yet so components have to be inserted, the
+    /// edge is inserted by a command the next time commands are applied.
+    Deferred,
+}
+
+/// Edits edges of kind `T` from inside a system keeping both sides consistent, as opposed to
+/// [`EntityCommandsExt`] where edits are only visible after commands are applied.
+///
+/// Components cannot be inserted or removed from inside a system. Inserts that give an entity its
+/// first edge of kind `T` are checked for errors up front and then deferred to a command,
+/// reported as [`Applied::Deferred`]. Should the world change before commands are applied so that
+/// the insert fails after all a warning is logged, like for
+/// [`EntityCommandsExt::try_insert_relation`]. Removing an entity's last edge leaves it with an
+/// empty storage that is removed by a command the next time commands are applied, until then
+/// entities with no edges may still match filters such as [`WithRelation`](crate::WithRelation).
+/// Restrictions, cycle checks and [`RelationEvent`]s apply the same as for other edits.
+#[derive(SystemParam)]
+pub struct RelationsMut<'w, 's, T: RelKind> {
+    relations: Query<'w, 's, RelationEdgesMut<T>>,
+    noitalers: Query<'w, 's, NoitalerMut<T>>,
+    entities: &'w Entities,
+    events: Option<ResMut<'w, Events<RelationEvent<T>>>>,
+    commands: Commands<'w, 's>,
+}
+impl<'w, 's, T: RelKind> RelationsMut<'w, 's, T> {
+    /// See [`Relations::get`]
+    pub fn get(&self, source: Entity) -> impl Iterator<Item = (T::Target, &T)> + '_ {
+        self.relations
+            .get(source)
+            .ok()
+            .into_iter()
+            .flat_map(|item| {
+                let (data, targets) = T::SourceRestriction::rel_iter(&item.inner.0);
+                targets.cloned().zip(data)
+            })
+    }
+
+    /// Returns the targets of `source` along with mutable access to the data of each edge
+    pub fn get_mut(&mut self, source: Entity) -> impl Iterator<Item = (T::Target, &mut T)> + '_ {
+        self.relations
+            .get_mut(source)
+            .ok()
+            .into_iter()
+            .flat_map(|item| {
+                let (data, targets) =
+                    T::SourceRestriction::rel_iter_mut(&mut item.inner.into_inner().0);
+                targets.cloned().zip(data)
+            })
+    }
+
+    pub fn targets(&self, source: Entity) -> impl Iterator<Item = T::Target> + '_ {
+        self.get(source).map(|(target, _)| target)
+    }
+
+    pub fn sources(&self, target: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.noitalers
+            .get(target)
+            .ok()
+            .into_iter()
+            .flat_map(|item| T::TargetRestriction::noi_iter(&item.inner.0))
+    }
+
+    /// See [`Relations::data`]
+    pub fn data(&self, source: Entity, target: T::Target) -> Option<&T> {
+        let rel = self.relations.get(source).ok()?.inner;
+        T::SourceRestriction::rel_get(&rel.0, &target)
+    }
+
+    pub fn data_mut(&mut self, source: Entity, target: T::Target) -> Option<&mut T> {
+        let rel = self.relations.get_mut(source).ok()?.inner.into_inner();
+        T::SourceRestriction::rel_get_mut(&mut rel.0, &target)
+    }
+
+    /// See [`Relations::has`]
+    pub fn has(&self, source: Entity, target: T::Target) -> bool {
+        self.data(source, target).is_some()
+    }
+
+    /// Inserts the edge `source -> target` evicting edges to uphold the restrictions of `T`, on
+    /// error nothing is changed. See [`EntityMutExt::try_insert_relation`].
+    ///
+    /// [`EntityMutExt::try_insert_relation`]: crate::EntityMutExt::try_insert_relation
+    pub fn insert(
+        &mut self,
+        source: Entity,
+        data: T,
+        target: T::Target,
+    ) -> Result<Applied<T>, RelationError> {
+        if !self.entities.contains(source) {
+            return Err(RelationError::DeadSource(source));
+        }
+        if let Some(target_entity) = target.as_entity() {
+            if !self.entities.contains(target_entity) {
+                return Err(RelationError::DeadTarget(target_entity));
+            }
+            if let Some(path) = T::Cyclicity::find_cycle(&*self, source, target_entity) {
+                return Err(match path.len() {
+                    1 => RelationError::SelfEdge(source),
+                    _ => RelationError::Cycle(path),
+                });
+            }
+        }
+
+        crate::check_rejected::<T>(
+            self.relations.get(source).ok().map(|item| item.inner),
+            target
+                .as_entity()
+                .and_then(|target| self.noitalers.get(target).ok())
+                .map(|item| item.inner),
+            source,
+            &target,
+        )?;
+
+        let mirror = T::Symmetry::mirror(&data);
+        let has_storage = self.has_storage(source, &target)
+            && match mirror.is_some() {
+                // symmetric kinds always target entities
+                true => self.has_storage(
+                    target.as_entity().unwrap(),
+                    &T::Target::from_entity(source).unwrap(),
+                ),
+                false => true,
+            };
+        if !has_storage {
+            self.commands
+                .entity(source)
+                .try_insert_relation(data, target);
+            return Ok(Applied::Deferred);
+        }
+
+        let outcome = self.insert_edge(source, data, target.clone());
+        if let Some(mirror) = mirror {
+            if let Some((old_target, _)) = &outcome.evicted_target {
+                self.remove_mirror(source, old_target);
+            }
+            if let Some((old_source, _)) = &outcome.evicted_source {
+                self.remove_mirror(*old_source, &target);
+            }
+            let target_entity = target.as_entity().unwrap();
+            if target_entity != source {
+                // the evictions above made room for the mirror on both entities so this can only
+                // replace the data of an existing mirror
+                let source = T::Target::from_entity(source).unwrap();
+                self.insert_edge(target_entity, mirror, source);
+            }
+        }
+
+        if self.events.is_some() {
+            self.send_events(insert_events(source, target, outcome.events_without_data()));
+        }
+        Ok(Applied::Immediately(outcome))
+    }
+
+    /// Removes the edge `source -> target`, returns `false` if there was no such edge
+    pub fn remove(&mut self, source: Entity, target: T::Target) -> bool {
+        let data = match self.remove_edge(source, &target) {
+            Some(data) => data,
+            None => return false,
+        };
+        let mirror = self.remove_mirror(source, &target);
+
+        let removed = std::iter::once((source, target, Some(data))).chain(mirror);
+        self.send_events(
+            removed.map(|(source, target, data)| RelationEvent::Removed {
+                source,
+                target,
+                data,
+            }),
+        );
+        true
+    }
+
+    /// Whether `source` and `target` have the storages needed to store `source -> target`
+    fn has_storage(&self, source: Entity, target: &T::Target) -> bool {
+        self.relations.contains(source)
+            && target
+                .as_entity()
+                .is_none_or(|target| self.noitalers.contains(target))
+    }
+
+    /// Inserts `source -> target` without mirroring it, both entities must have storages
+    fn insert_edge(&mut self, source: Entity, data: T, target: T::Target) -> InsertOutcome<T> {
+        let mut outcome = InsertOutcome {
+            replaced: None,
+            evicted_target: None,
+            evicted_source: None,
+        };
+
+        let mut rel = self.relations.get_mut(source).unwrap();
+        match T::SourceRestriction::push_rel(&mut rel.inner.0, data, target.clone()) {
+            Some((old_target, old_data)) if old_target == target => {
+                outcome.replaced = Some(old_data);
+                return outcome;
+            }
+            Some((old_target, old_data)) => {
+                rel.edges.set_changed();
+                if let Some(old_target) = old_target.as_entity() {
+                    self.remove_noi_entry(old_target, source);
+                }
+                outcome.evicted_target = Some((old_target, old_data));
+            }
+            None => rel.edges.set_changed(),
+        }
+
+        if let Some(target_entity) = target.as_entity() {
+            let mut noi = self.noitalers.get_mut(target_entity).unwrap();
+            if let Some(old_source) = T::TargetRestriction::push_noi(&mut noi.inner.0, source) {
+                let mut rel = self.relations.get_mut(old_source).unwrap();
+                let (old_data, is_empty) = T::SourceRestriction::remove_rel(
+                    &mut rel.inner.bypass_change_detection().0,
+                    &target,
+                )
+                .unwrap();
+                rel.edges.set_changed();
+                if is_empty {
+                    self.remove_if_empty(old_source);
+                }
+                outcome.evicted_source = Some((old_source, old_data));
+            }
+        }
+
+        outcome
+    }
+
+    /// Removes `source -> target` without removing its mirror, storages that end up empty are
+    /// removed once commands are applied
+    fn remove_edge(&mut self, source: Entity, target: &T::Target) -> Option<T> {
+        let mut rel = self.relations.get_mut(source).ok()?;
+        // removing an edge does not change the data of the remaining edges
+        let (data, is_empty) =
+            T::SourceRestriction::remove_rel(&mut rel.inner.bypass_change_detection().0, target)?;
+        rel.edges.set_changed();
+        if is_empty {
+            self.remove_if_empty(source);
+        }
+        if let Some(target) = target.as_entity() {
+            self.remove_noi_entry(target, source);
+        }
+        Some(data)
+    }
+
+    /// Removes `source` from the `Noitaler<T>` of `target` without updating the `Relation<T>` of
+    /// `source`
+    fn remove_noi_entry(&mut self, target: Entity, source: Entity) {
+        if let Ok(mut noi) = self.noitalers.get_mut(target) {
+            if let Some(true) = T::TargetRestriction::remove_noi(&mut noi.inner.0, source) {
+                self.remove_if_empty(target);
+            }
+        }
+    }
+
+    /// Removes the `target -> source` edge mirroring `source -> target` if `T` is
+    /// [`symmetry::Symmetric`](crate::symmetry::Symmetric)
+    fn remove_mirror(
+        &mut self,
+        source: Entity,
+        target: &T::Target,
+    ) -> Option<(Entity, T::Target, Option<T>)> {
+        if !T::Symmetry::SYMMETRIC {
+            return None;
+        }
+        let target = target.as_entity().filter(|&target| target != source)?;
+        let source = T::Target::from_entity(source).unwrap();
+        let data = self.remove_edge(target, &source)?;
+        Some((target, source, Some(data)))
+    }
+
+    fn remove_if_empty(&mut self, entity: Entity) {
+        self.commands
+            .add(move |world: &mut World| remove_empty_storages::<T>(world, entity));
+    }
+
+    fn send_events(&mut self, events: impl IntoIterator<Item = RelationEvent<T>>) {
+        if let Some(sender) = &mut self.events {
+            for event in events {
+                sender.send(event);
+            }
+        }
+    }
+}
+
+/// Removes the storages of `entity` that were emptied by [`RelationsMut`] unless edges were
+/// inserted into them since
+fn remove_empty_storages<T: RelKind>(world: &mut World, entity: Entity) {
+    let mut entity = match world.get_entity_mut(entity) {
+        Some(entity) => entity,
+        None => return,
+    };
+    if entity
+        .get::<Relation<T>>()
+        .is_some_and(|rel| T::SourceRestriction::rel_iter(&rel.0).1.next().is_none())
+    {
+        entity.remove::<(Relation<T>, RelationEdges<T>)>();
+    }
+    if entity
+        .get::<Noitaler<T>>()
+        .is_some_and(|noi| T::TargetRestriction::noi_iter(&noi.0).next().is_none())
+    {
+        entity.remove::<Noitaler<T>>();
+    }
+}
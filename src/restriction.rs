@@ -1,69 +1,213 @@
-use bevy::prelude::Entity;
+use std::{cmp::Ordering, marker::PhantomData};
+
+use arrayvec::ArrayVec;
+use bevy::{prelude::Entity, utils::HashMap};
 
 use crate::RelKind;
 
 pub struct One;
-pub struct Many;
+/// Allows any number of edges, `O` decides whether removing an edge keeps the remaining edges in
+/// order, see [`Unordered`] and [`Ordered`].
+pub struct Many<O = Unordered>(PhantomData<O>);
+/// Like [`Many`] but also keeps a hash index of the edges so that finding, inserting and removing
+/// an edge takes constant time instead of scanning every edge. Edges are still stored
+/// contiguously for iteration. Prefer [`Many`] unless entities have hundreds of edges, the index
+/// makes small storages slower and larger.
+pub struct ManyIndexed;
+/// Allows up to `N` edges, `N` must be at least 1 which is checked at compile time. Edges are
+/// stored inline and kept in the order they were inserted in unless reordered through
+/// [`OrderedRestriction`], `E` decides what happens when inserting into a full storage, see
+/// [`EvictOldest`], [`EvictNewest`] and [`Reject`].
+pub struct AtMost<const N: usize, E = EvictOldest>(PhantomData<E>);
+impl<const N: usize, E> AtMost<N, E> {
+    // evaluated when a storage is created so that `AtMost<0>` fails to compile once it is used
+    const NOT_EMPTY: () = assert!(N > 0, "`AtMost<N>` must allow at least 1 edge");
+}
 
-pub trait Restriction<T: RelKind>: crate::sealed::Sealed {
+/// Removing an edge from [`Many`] moves the last edge into its place
+pub struct Unordered;
+/// Edges of [`Many`] stay in the order they were inserted in unless reordered through
+/// [`OrderedRestriction`], removing an edge is linear in the number of edges after it.
+pub struct Ordered;
+
+pub trait EdgeOrder: crate::sealed::Sealed {
     #[doc(hidden)]
-    type RelStorage: Send + Sync + 'static;
+    const ORDERED: bool;
+}
+impl EdgeOrder for Unordered {
+    const ORDERED: bool = false;
+}
+impl EdgeOrder for Ordered {
+    const ORDERED: bool = true;
+}
+
+/// Inserting into a full [`AtMost`] evicts its first edge, the one that was inserted first
+/// unless the edges were reordered
+pub struct EvictOldest;
+/// Inserting into a full [`AtMost`] evicts its last edge, the one that was inserted last unless
+/// the edges were reordered
+pub struct EvictNewest;
+/// Inserting into a full [`AtMost`] fails with [`RelationError::SourceFull`] or
+/// [`RelationError::TargetFull`]
+///
+/// [`RelationError::SourceFull`]: crate::RelationError::SourceFull
+/// [`RelationError::TargetFull`]: crate::RelationError::TargetFull
+pub struct Reject;
+
+pub trait Eviction: crate::sealed::Sealed {
+    /// Index of the edge to evict from a full storage of `len` edges, `None` rejects the insert
     #[doc(hidden)]
+    fn evict_index(len: usize) -> Option<usize>;
+}
+impl Eviction for EvictOldest {
+    fn evict_index(_: usize) -> Option<usize> {
+        Some(0)
+    }
+}
+impl Eviction for EvictNewest {
+    fn evict_index(len: usize) -> Option<usize> {
+        Some(len - 1)
+    }
+}
+impl Eviction for Reject {
+    fn evict_index(_: usize) -> Option<usize> {
+        None
+    }
+}
+
+/// Limits how many edges of kind `T` an entity can have and stores them. Used as
+/// [`RelKind::SourceRestriction`] where each entity has a `RelStorage` holding the targets and
+/// data of its edges, and as [`RelKind::TargetRestriction`] where each entity has a `NoiStorage`
+/// holding the sources of the edges pointing to it.
+///
+/// Storages are created for an entity's first edge and removed once they report being empty.
+/// [`RelationsMut`](crate::RelationsMut) cannot remove components so storages it empties are kept
+/// until commands are applied, so empty storages have to be iterable and accept pushes like any
+/// other storage. Every edge is stored at most once, an entity being inserted again replaces the
+/// existing edge. When an insert would exceed the restriction the storage either evicts one of its
+/// edges and returns it, or rejects the insert up front through
+/// [`Restriction::rel_rejects`]/[`Restriction::noi_rejects`].
+///
+/// Implementations can be checked against these requirements with
+/// [`conformance::check_restriction`](crate::conformance::check_restriction).
+pub trait Restriction<T: RelKind> {
+    /// Targets and data of the edges out of an entity
+    type RelStorage: Send + Sync + 'static;
+    /// Sources of the edges into an entity
     type NoiStorage: Send + Sync + 'static;
-    #[doc(hidden)]
-    fn make_rel_storage(data: T, target: Entity) -> Self::RelStorage;
-    #[doc(hidden)]
-    fn make_noi_storage(target: Entity) -> Self::NoiStorage;
-    #[doc(hidden)]
-    fn push_rel(rel: &mut Self::RelStorage, data: T, target: Entity) -> Option<Entity>;
-    #[doc(hidden)]
-    fn push_noi(noi: &mut Self::NoiStorage, target: Entity) -> Option<Entity>;
-    #[doc(hidden)]
-    fn remove_rel(rel: &mut Self::RelStorage, target: Entity) -> bool;
-    #[doc(hidden)]
-    fn remove_noi(noi: &mut Self::NoiStorage, target: Entity) -> bool;
+    /// Creates a storage containing only the edge to `target`
+    fn make_rel_storage(data: T, target: T::Target) -> Self::RelStorage;
+    /// Creates a storage containing only the edge from `source`
+    fn make_noi_storage(source: Entity) -> Self::NoiStorage;
+    /// Returns the edge that was overwritten, this is either `target` with its previous data
+    /// or some other target that had to be evicted to uphold the restriction.
+    fn push_rel(rel: &mut Self::RelStorage, data: T, target: T::Target) -> Option<(T::Target, T)>;
+    /// Returns the source that had to be evicted to uphold the restriction, pushing a source
+    /// that is already stored does nothing.
+    fn push_noi(noi: &mut Self::NoiStorage, source: Entity) -> Option<Entity>;
+    /// The target of the edge that pushing an edge to a new target would evict, must agree with
+    /// [`Restriction::push_rel`]
+    fn rel_to_evict(rel: &Self::RelStorage) -> Option<&T::Target>;
+    /// The source of the edge that pushing an edge from a new source would evict, must agree with
+    /// [`Restriction::push_noi`]
+    fn noi_to_evict(noi: &Self::NoiStorage) -> Option<Entity>;
+    /// Whether pushing an edge to `target` has to be rejected, checked before calling `push_rel`
+    fn rel_rejects(rel: &Self::RelStorage, target: &T::Target) -> bool;
+    /// Whether pushing an edge from `source` has to be rejected, checked before calling `push_noi`
+    fn noi_rejects(noi: &Self::NoiStorage, source: Entity) -> bool;
+    /// Returns `None` if there was no edge to `target`, otherwise the data of the removed edge
+    /// and whether the storage is now empty and should be removed.
+    fn remove_rel(rel: &mut Self::RelStorage, target: &T::Target) -> Option<(T, bool)>;
+    /// Returns `None` if there was no edge from `source`, otherwise whether the storage is now
+    /// empty and should be removed.
+    fn remove_noi(noi: &mut Self::NoiStorage, source: Entity) -> Option<bool>;
+
+    /// The data of the edge to `target`, storages that can find an edge faster than iterating
+    /// every edge should override this
+    fn rel_get<'a>(rel: &'a Self::RelStorage, target: &T::Target) -> Option<&'a T> {
+        let (data, targets) = Self::rel_iter(rel);
+        targets
+            .zip(data)
+            .find_map(|(cur_target, data)| (cur_target == target).then_some(data))
+    }
+    /// See [`Restriction::rel_get`]
+    fn rel_get_mut<'a>(rel: &'a mut Self::RelStorage, target: &T::Target) -> Option<&'a mut T> {
+        let (data, targets) = Self::rel_iter_mut(rel);
+        targets
+            .zip(data)
+            .find_map(|(cur_target, data)| (cur_target == target).then_some(data))
+    }
+    /// Whether there is an edge from `source`, storages that can find an edge faster than
+    /// iterating every edge should override this
+    fn noi_contains(noi: &Self::NoiStorage, source: Entity) -> bool {
+        Self::noi_iter(noi).any(|cur_source| cur_source == source)
+    }
+
+    /// Whether a storage can never hold more than one edge, this lets
+    /// [`cyclicity::Acyclic`](crate::cyclicity::Acyclic) kinds check for cycles by walking a
+    /// single path instead of searching the graph.
+    const AT_MOST_ONE: bool = false;
 
-    #[doc(hidden)]
     type RelDataIterMut<'a>: Iterator<Item = &'a mut T>;
-    #[doc(hidden)]
     type RelDataIter<'a>: Iterator<Item = &'a T>;
-    #[doc(hidden)]
-    type RelTargetIter<'a>: Iterator<Item = Entity>;
-    #[doc(hidden)]
+    type RelTargetIter<'a>: Iterator<Item = &'a T::Target>;
+    /// Iterates the data and targets of every edge, both iterators must yield edges in the same
+    /// order
     fn rel_iter_mut(
         rel: &mut Self::RelStorage,
     ) -> (Self::RelDataIterMut<'_>, Self::RelTargetIter<'_>);
-    #[doc(hidden)]
+    /// Iterates the data and targets of every edge, both iterators must yield edges in the same
+    /// order
     fn rel_iter(rel: &Self::RelStorage) -> (Self::RelDataIter<'_>, Self::RelTargetIter<'_>);
+    type RelIntoIter: Iterator<Item = (T, T::Target)>;
+    fn rel_into_iter(rel: Self::RelStorage) -> Self::RelIntoIter;
 
-    #[doc(hidden)]
-    type NoiTargetIter<'a>: Iterator<Item = Entity>;
-    #[doc(hidden)]
-    fn noi_iter(noi: &Self::NoiStorage) -> Self::NoiTargetIter<'_>;
+    type NoiSourceIter<'a>: Iterator<Item = Entity>;
+    fn noi_iter(noi: &Self::NoiStorage) -> Self::NoiSourceIter<'_>;
 }
-impl<T: RelKind> Restriction<T> for Many {
-    type RelStorage = (Vec<T>, Vec<Entity>);
+impl<T: RelKind, O: EdgeOrder> Restriction<T> for Many<O> {
+    type RelStorage = (Vec<T>, Vec<T::Target>);
     type NoiStorage = Vec<Entity>;
 
-    fn push_rel(rel: &mut (Vec<T>, Vec<Entity>), data: T, target: Entity) -> Option<Entity> {
+    fn push_rel(
+        rel: &mut (Vec<T>, Vec<T::Target>),
+        data: T,
+        target: T::Target,
+    ) -> Option<(T::Target, T)> {
         match rel.1.iter().position(|target2| *target2 == target) {
-            Some(pos) => rel.0[pos] = data,
+            Some(pos) => Some((target, std::mem::replace(&mut rel.0[pos], data))),
             None => {
                 rel.0.push(data);
                 rel.1.push(target);
+                None
             }
         }
-        None
     }
 
     fn push_noi(noi: &mut Vec<Entity>, target: Entity) -> Option<Entity> {
-        if let None = noi.iter().find(|target2| **target2 == target) {
+        if !noi.contains(&target) {
             noi.push(target);
         }
         None
     }
 
-    fn make_rel_storage(data: T, target: Entity) -> Self::RelStorage {
+    fn rel_to_evict(_: &(Vec<T>, Vec<T::Target>)) -> Option<&T::Target> {
+        None
+    }
+
+    fn noi_to_evict(_: &Vec<Entity>) -> Option<Entity> {
+        None
+    }
+
+    fn rel_rejects(_: &(Vec<T>, Vec<T::Target>), _: &T::Target) -> bool {
+        false
+    }
+
+    fn noi_rejects(_: &Vec<Entity>, _: Entity) -> bool {
+        false
+    }
+
+    fn make_rel_storage(data: T, target: T::Target) -> Self::RelStorage {
         (vec![data], vec![target])
     }
 
@@ -71,104 +215,537 @@ impl<T: RelKind> Restriction<T> for Many {
         vec![target]
     }
 
-    fn remove_rel(rel: &mut (Vec<T>, Vec<Entity>), target: Entity) -> bool {
-        if rel.0.len() == 1 {
-            return true;
-        }
+    fn remove_rel(rel: &mut (Vec<T>, Vec<T::Target>), target: &T::Target) -> Option<(T, bool)> {
+        let pos = rel.1.iter().position(|target2| target2 == target)?;
+        let data = match O::ORDERED {
+            true => {
+                rel.1.remove(pos);
+                rel.0.remove(pos)
+            }
+            false => {
+                rel.1.swap_remove(pos);
+                rel.0.swap_remove(pos)
+            }
+        };
+
+        Some((data, rel.1.is_empty()))
+    }
+
+    fn remove_noi(noi: &mut Vec<Entity>, target: Entity) -> Option<bool> {
+        let pos = noi.iter().position(|target2| *target2 == target)?;
+        match O::ORDERED {
+            true => noi.remove(pos),
+            false => noi.swap_remove(pos),
+        };
+
+        Some(noi.is_empty())
+    }
+
+    type RelDataIterMut<'a> = std::slice::IterMut<'a, T>;
+    type RelDataIter<'a> = std::slice::Iter<'a, T>;
+    type RelTargetIter<'a> = std::slice::Iter<'a, T::Target>;
+    fn rel_iter_mut(
+        rel: &mut Self::RelStorage,
+    ) -> (Self::RelDataIterMut<'_>, Self::RelTargetIter<'_>) {
+        (rel.0.iter_mut(), rel.1.iter())
+    }
+    fn rel_iter(rel: &Self::RelStorage) -> (Self::RelDataIter<'_>, Self::RelTargetIter<'_>) {
+        (rel.0.iter(), rel.1.iter())
+    }
+    type RelIntoIter = std::iter::Zip<std::vec::IntoIter<T>, std::vec::IntoIter<T::Target>>;
+    fn rel_into_iter(rel: Self::RelStorage) -> Self::RelIntoIter {
+        rel.0.into_iter().zip(rel.1)
+    }
+
+    type NoiSourceIter<'a> = std::iter::Copied<std::slice::Iter<'a, Entity>>;
+    fn noi_iter(noi: &Self::NoiStorage) -> Self::NoiSourceIter<'_> {
+        noi.iter().copied()
+    }
+}
+impl<T: RelKind> Restriction<T> for One {
+    const AT_MOST_ONE: bool = true;
 
-        let pos = rel.1.iter().position(|target2| *target2 == target).unwrap();
-        rel.0.swap_remove(pos);
-        rel.1.swap_remove(pos);
+    // `None` only exists between removing the last edge and removing the storage
+    type RelStorage = Option<(T, T::Target)>;
+    type NoiStorage = Option<Entity>;
+    fn push_rel(
+        rel: &mut Option<(T, T::Target)>,
+        data: T,
+        target: T::Target,
+    ) -> Option<(T::Target, T)> {
+        // drop/panic safety?
+        rel.replace((data, target))
+            .map(|(old_data, old_target)| (old_target, old_data))
+    }
+    fn push_noi(noi: &mut Option<Entity>, source: Entity) -> Option<Entity> {
+        noi.replace(source)
+            .filter(|&old_source| old_source != source)
+    }
+
+    fn rel_to_evict(rel: &Option<(T, T::Target)>) -> Option<&T::Target> {
+        rel.as_ref().map(|(_, target)| target)
+    }
 
+    fn noi_to_evict(noi: &Option<Entity>) -> Option<Entity> {
+        *noi
+    }
+
+    fn rel_rejects(_: &Option<(T, T::Target)>, _: &T::Target) -> bool {
         false
     }
 
-    fn remove_noi(noi: &mut Vec<Entity>, target: Entity) -> bool {
-        if noi.len() == 1 {
-            return true;
+    fn noi_rejects(_: &Option<Entity>, _: Entity) -> bool {
+        false
+    }
+
+    fn make_rel_storage(data: T, target: T::Target) -> Option<(T, T::Target)> {
+        Some((data, target))
+    }
+
+    fn make_noi_storage(source: Entity) -> Option<Entity> {
+        Some(source)
+    }
+
+    fn remove_rel(rel: &mut Option<(T, T::Target)>, target: &T::Target) -> Option<(T, bool)> {
+        match rel {
+            Some((_, cur_target)) if cur_target == target => Some((rel.take()?.0, true)),
+            _ => None,
         }
+    }
 
-        let pos = noi.iter().position(|target2| *target2 == target).unwrap();
-        noi.swap_remove(pos);
+    fn remove_noi(noi: &mut Option<Entity>, source: Entity) -> Option<bool> {
+        noi.take_if(|cur_source| *cur_source == source)
+            .map(|_| true)
+    }
 
-        false
+    type RelDataIterMut<'a> = std::option::IntoIter<&'a mut T>;
+    type RelDataIter<'a> = std::option::IntoIter<&'a T>;
+    type RelTargetIter<'a> = std::option::IntoIter<&'a T::Target>;
+    fn rel_iter_mut(
+        rel: &mut Self::RelStorage,
+    ) -> (Self::RelDataIterMut<'_>, Self::RelTargetIter<'_>) {
+        match rel {
+            Some((data, target)) => (Some(data).into_iter(), Some(&*target).into_iter()),
+            None => (None.into_iter(), None.into_iter()),
+        }
+    }
+    fn rel_iter(rel: &Self::RelStorage) -> (Self::RelDataIter<'_>, Self::RelTargetIter<'_>) {
+        (
+            rel.as_ref().map(|(data, _)| data).into_iter(),
+            rel.as_ref().map(|(_, target)| target).into_iter(),
+        )
+    }
+    type RelIntoIter = std::option::IntoIter<(T, T::Target)>;
+    fn rel_into_iter(rel: Self::RelStorage) -> Self::RelIntoIter {
+        rel.into_iter()
+    }
+
+    type NoiSourceIter<'a> = std::option::IntoIter<Entity>;
+    fn noi_iter(noi: &Self::NoiStorage) -> Self::NoiSourceIter<'_> {
+        (*noi).into_iter()
+    }
+}
+impl<T: RelKind, const N: usize, E: Eviction> Restriction<T> for AtMost<N, E> {
+    const AT_MOST_ONE: bool = N == 1;
+
+    type RelStorage = (ArrayVec<T, N>, ArrayVec<T::Target, N>);
+    type NoiStorage = ArrayVec<Entity, N>;
+
+    fn push_rel(
+        rel: &mut (ArrayVec<T, N>, ArrayVec<T::Target, N>),
+        data: T,
+        target: T::Target,
+    ) -> Option<(T::Target, T)> {
+        if let Some(pos) = rel.1.iter().position(|target2| *target2 == target) {
+            return Some((target, std::mem::replace(&mut rel.0[pos], data)));
+        }
+        let evicted = match rel.1.is_full() {
+            true => {
+                let pos = E::evict_index(N).expect("rejected inserts are checked before pushing");
+                Some((rel.1.remove(pos), rel.0.remove(pos)))
+            }
+            false => None,
+        };
+        rel.0.push(data);
+        rel.1.push(target);
+        evicted
+    }
+
+    fn push_noi(noi: &mut ArrayVec<Entity, N>, target: Entity) -> Option<Entity> {
+        if noi.contains(&target) {
+            return None;
+        }
+        let evicted = match noi.is_full() {
+            true => {
+                let pos = E::evict_index(N).expect("rejected inserts are checked before pushing");
+                Some(noi.remove(pos))
+            }
+            false => None,
+        };
+        noi.push(target);
+        evicted
+    }
+
+    fn rel_to_evict(rel: &(ArrayVec<T, N>, ArrayVec<T::Target, N>)) -> Option<&T::Target> {
+        match rel.1.is_full() {
+            true => E::evict_index(N).map(|pos| &rel.1[pos]),
+            false => None,
+        }
+    }
+
+    fn noi_to_evict(noi: &ArrayVec<Entity, N>) -> Option<Entity> {
+        match noi.is_full() {
+            true => E::evict_index(N).map(|pos| noi[pos]),
+            false => None,
+        }
+    }
+
+    fn rel_rejects(rel: &(ArrayVec<T, N>, ArrayVec<T::Target, N>), target: &T::Target) -> bool {
+        rel.1.is_full() && E::evict_index(N).is_none() && !rel.1.contains(target)
+    }
+
+    fn noi_rejects(noi: &ArrayVec<Entity, N>, source: Entity) -> bool {
+        noi.is_full() && E::evict_index(N).is_none() && !noi.contains(&source)
+    }
+
+    fn make_rel_storage(data: T, target: T::Target) -> Self::RelStorage {
+        let () = Self::NOT_EMPTY;
+        let mut rel = (ArrayVec::new(), ArrayVec::new());
+        rel.0.push(data);
+        rel.1.push(target);
+        rel
+    }
+
+    fn make_noi_storage(target: Entity) -> Self::NoiStorage {
+        let () = Self::NOT_EMPTY;
+        let mut noi = ArrayVec::new();
+        noi.push(target);
+        noi
+    }
+
+    // `remove` rather than `swap_remove` to keep edges in insertion order for `E`
+    fn remove_rel(
+        rel: &mut (ArrayVec<T, N>, ArrayVec<T::Target, N>),
+        target: &T::Target,
+    ) -> Option<(T, bool)> {
+        let pos = rel.1.iter().position(|target2| target2 == target)?;
+        let data = rel.0.remove(pos);
+        rel.1.remove(pos);
+
+        Some((data, rel.1.is_empty()))
+    }
+
+    fn remove_noi(noi: &mut ArrayVec<Entity, N>, target: Entity) -> Option<bool> {
+        let pos = noi.iter().position(|target2| *target2 == target)?;
+        noi.remove(pos);
+
+        Some(noi.is_empty())
     }
 
     type RelDataIterMut<'a> = std::slice::IterMut<'a, T>;
     type RelDataIter<'a> = std::slice::Iter<'a, T>;
-    type RelTargetIter<'a> = std::iter::Copied<std::slice::Iter<'a, Entity>>;
+    type RelTargetIter<'a> = std::slice::Iter<'a, T::Target>;
     fn rel_iter_mut(
         rel: &mut Self::RelStorage,
     ) -> (Self::RelDataIterMut<'_>, Self::RelTargetIter<'_>) {
-        (rel.0.iter_mut(), rel.1.iter().copied())
+        (rel.0.iter_mut(), rel.1.iter())
     }
     fn rel_iter(rel: &Self::RelStorage) -> (Self::RelDataIter<'_>, Self::RelTargetIter<'_>) {
-        (rel.0.iter(), rel.1.iter().copied())
+        (rel.0.iter(), rel.1.iter())
+    }
+    type RelIntoIter = std::iter::Zip<arrayvec::IntoIter<T, N>, arrayvec::IntoIter<T::Target, N>>;
+    fn rel_into_iter(rel: Self::RelStorage) -> Self::RelIntoIter {
+        rel.0.into_iter().zip(rel.1)
     }
 
-    type NoiTargetIter<'a> = std::iter::Copied<std::slice::Iter<'a, Entity>>;
-    fn noi_iter(noi: &Self::NoiStorage) -> Self::NoiTargetIter<'_> {
+    type NoiSourceIter<'a> = std::iter::Copied<std::slice::Iter<'a, Entity>>;
+    fn noi_iter(noi: &Self::NoiStorage) -> Self::NoiSourceIter<'_> {
         noi.iter().copied()
     }
 }
-impl<T: RelKind> Restriction<T> for One {
-    type RelStorage = (T, Entity);
-    type NoiStorage = Entity;
-    fn push_rel(rel: &mut (T, Entity), data: T, target: Entity) -> Option<Entity> {
-        match rel.1 == target {
-            true => {
-                rel.0 = data;
-                None
-            }
-            false => {
-                let old_target = rel.1;
-                // drop/panic safety?
-                *rel = (data, target);
-                Some(old_target)
+
+/// A [`Restriction`] whose edges have a meaningful order that can be changed, implemented by
+/// [`Many<Ordered>`](Many) and [`AtMost`]. The order is the one edges are iterated in.
+pub trait OrderedRestriction<T: RelKind>: Restriction<T> {
+    /// Moves the edge to `target` so that it ends up at `index`, shifting the edges in between.
+    /// Returns `false` if there is no such edge.
+    ///
+    /// Panics if there is such an edge and `index` is out of bounds.
+    fn rel_move(rel: &mut Self::RelStorage, target: &T::Target, index: usize) -> bool;
+    /// See [`OrderedRestriction::rel_move`]
+    fn noi_move(noi: &mut Self::NoiStorage, source: Entity, index: usize) -> bool;
+    /// Stable sort of the edges by their targets and data, returns `false` if the edges were
+    /// already sorted and nothing moved
+    fn rel_sort_by<F>(rel: &mut Self::RelStorage, compare: F) -> bool
+    where
+        F: FnMut((&T::Target, &T), (&T::Target, &T)) -> Ordering;
+    /// Stable sort of the edges by their sources, see [`OrderedRestriction::rel_sort_by`]
+    fn noi_sort_by<F>(noi: &mut Self::NoiStorage, compare: F) -> bool
+    where
+        F: FnMut(Entity, Entity) -> Ordering;
+}
+
+fn move_within<X>(edges: &mut [X], from: usize, to: usize) {
+    assert!(
+        to < edges.len(),
+        "index {} is out of bounds for {} edges",
+        to,
+        edges.len()
+    );
+    match from < to {
+        true => edges[from..=to].rotate_left(1),
+        false => edges[to..=from].rotate_right(1),
+    }
+}
+
+/// Whether a stable sort would leave every edge where it is
+fn is_sorted<D, G, F>(data: &[D], targets: &[G], compare: &mut F) -> bool
+where
+    F: FnMut((&G, &D), (&G, &D)) -> Ordering,
+{
+    (1..targets.len()).all(|i| {
+        compare((&targets[i - 1], &data[i - 1]), (&targets[i], &data[i])) != Ordering::Greater
+    })
+}
+
+fn sorted_edges<D, G, F>(edges: impl Iterator<Item = (D, G)>, mut compare: F) -> Vec<(D, G)>
+where
+    F: FnMut((&G, &D), (&G, &D)) -> Ordering,
+{
+    let mut edges = edges.collect::<Vec<_>>();
+    edges.sort_by(|(data1, target1), (data2, target2)| compare((target1, data1), (target2, data2)));
+    edges
+}
+
+fn sort_sources<F>(sources: &mut [Entity], mut compare: F) -> bool
+where
+    F: FnMut(Entity, Entity) -> Ordering,
+{
+    if sources
+        .windows(2)
+        .all(|pair| compare(pair[0], pair[1]) != Ordering::Greater)
+    {
+        return false;
+    }
+    sources.sort_by(|source1, source2| compare(*source1, *source2));
+    true
+}
+
+impl<T: RelKind> OrderedRestriction<T> for Many<Ordered> {
+    fn rel_move(rel: &mut Self::RelStorage, target: &T::Target, index: usize) -> bool {
+        match rel.1.iter().position(|target2| target2 == target) {
+            Some(pos) => {
+                move_within(&mut rel.1, pos, index);
+                move_within(&mut rel.0, pos, index);
+                true
             }
+            None => false,
         }
     }
-    fn push_noi(noi: &mut Entity, target: Entity) -> Option<Entity> {
-        match *noi == target {
-            true => None,
-            false => {
-                let old_target = *noi;
-                *noi = target;
-                Some(old_target)
+
+    fn noi_move(noi: &mut Self::NoiStorage, source: Entity, index: usize) -> bool {
+        match noi.iter().position(|source2| *source2 == source) {
+            Some(pos) => {
+                move_within(noi, pos, index);
+                true
             }
+            None => false,
         }
     }
 
-    fn make_rel_storage(data: T, target: Entity) -> (T, Entity) {
-        (data, target)
+    fn rel_sort_by<F>(rel: &mut Self::RelStorage, mut compare: F) -> bool
+    where
+        F: FnMut((&T::Target, &T), (&T::Target, &T)) -> Ordering,
+    {
+        if is_sorted(&rel.0, &rel.1, &mut compare) {
+            return false;
+        }
+        let (data, targets) = std::mem::take(rel);
+        *rel = sorted_edges(data.into_iter().zip(targets), compare)
+            .into_iter()
+            .unzip();
+        true
     }
 
-    fn make_noi_storage(target: Entity) -> Entity {
-        target
+    fn noi_sort_by<F>(noi: &mut Self::NoiStorage, compare: F) -> bool
+    where
+        F: FnMut(Entity, Entity) -> Ordering,
+    {
+        sort_sources(noi, compare)
     }
+}
 
-    fn remove_rel(_rel: &mut (T, Entity), _target: Entity) -> bool {
-        true
+impl<T: RelKind, const N: usize, E: Eviction> OrderedRestriction<T> for AtMost<N, E> {
+    fn rel_move(rel: &mut Self::RelStorage, target: &T::Target, index: usize) -> bool {
+        match rel.1.iter().position(|target2| target2 == target) {
+            Some(pos) => {
+                move_within(&mut rel.1, pos, index);
+                move_within(&mut rel.0, pos, index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn noi_move(noi: &mut Self::NoiStorage, source: Entity, index: usize) -> bool {
+        match noi.iter().position(|source2| *source2 == source) {
+            Some(pos) => {
+                move_within(noi, pos, index);
+                true
+            }
+            None => false,
+        }
     }
 
-    fn remove_noi(_noi: &mut Entity, _target: Entity) -> bool {
+    fn rel_sort_by<F>(rel: &mut Self::RelStorage, mut compare: F) -> bool
+    where
+        F: FnMut((&T::Target, &T), (&T::Target, &T)) -> Ordering,
+    {
+        if is_sorted(&rel.0, &rel.1, &mut compare) {
+            return false;
+        }
+        let (data, targets) = std::mem::take(rel);
+        *rel = sorted_edges(data.into_iter().zip(targets), compare)
+            .into_iter()
+            .unzip();
         true
     }
 
-    type RelDataIterMut<'a> = std::iter::Once<&'a mut T>;
-    type RelDataIter<'a> = std::iter::Once<&'a T>;
-    type RelTargetIter<'a> = std::iter::Once<Entity>;
+    fn noi_sort_by<F>(noi: &mut Self::NoiStorage, compare: F) -> bool
+    where
+        F: FnMut(Entity, Entity) -> Ordering,
+    {
+        sort_sources(noi, compare)
+    }
+}
+
+/// [`Restriction::RelStorage`] of [`ManyIndexed`]
+pub struct IndexedRelStorage<T: RelKind> {
+    data: Vec<T>,
+    targets: Vec<T::Target>,
+    index: HashMap<T::Target, usize>,
+}
+
+/// [`Restriction::NoiStorage`] of [`ManyIndexed`]
+pub struct IndexedNoiStorage {
+    sources: Vec<Entity>,
+    index: HashMap<Entity, usize>,
+}
+
+impl<T: RelKind> Restriction<T> for ManyIndexed {
+    type RelStorage = IndexedRelStorage<T>;
+    type NoiStorage = IndexedNoiStorage;
+
+    fn push_rel(
+        rel: &mut IndexedRelStorage<T>,
+        data: T,
+        target: T::Target,
+    ) -> Option<(T::Target, T)> {
+        match rel.index.get(&target) {
+            Some(&pos) => Some((target, std::mem::replace(&mut rel.data[pos], data))),
+            None => {
+                rel.index.insert(target.clone(), rel.targets.len());
+                rel.data.push(data);
+                rel.targets.push(target);
+                None
+            }
+        }
+    }
+
+    fn push_noi(noi: &mut IndexedNoiStorage, source: Entity) -> Option<Entity> {
+        if !noi.index.contains_key(&source) {
+            noi.index.insert(source, noi.sources.len());
+            noi.sources.push(source);
+        }
+        None
+    }
+
+    fn rel_to_evict(_: &IndexedRelStorage<T>) -> Option<&T::Target> {
+        None
+    }
+
+    fn noi_to_evict(_: &IndexedNoiStorage) -> Option<Entity> {
+        None
+    }
+
+    fn rel_rejects(_: &IndexedRelStorage<T>, _: &T::Target) -> bool {
+        false
+    }
+
+    fn noi_rejects(_: &IndexedNoiStorage, _: Entity) -> bool {
+        false
+    }
+
+    fn make_rel_storage(data: T, target: T::Target) -> Self::RelStorage {
+        let mut index = HashMap::default();
+        index.insert(target.clone(), 0);
+        IndexedRelStorage {
+            data: vec![data],
+            targets: vec![target],
+            index,
+        }
+    }
+
+    fn make_noi_storage(source: Entity) -> Self::NoiStorage {
+        let mut index = HashMap::default();
+        index.insert(source, 0);
+        IndexedNoiStorage {
+            sources: vec![source],
+            index,
+        }
+    }
+
+    fn remove_rel(rel: &mut IndexedRelStorage<T>, target: &T::Target) -> Option<(T, bool)> {
+        let pos = rel.index.remove(target)?;
+        let data = rel.data.swap_remove(pos);
+        rel.targets.swap_remove(pos);
+        if let Some(moved) = rel.targets.get(pos) {
+            rel.index.insert(moved.clone(), pos);
+        }
+
+        Some((data, rel.targets.is_empty()))
+    }
+
+    fn remove_noi(noi: &mut IndexedNoiStorage, source: Entity) -> Option<bool> {
+        let pos = noi.index.remove(&source)?;
+        noi.sources.swap_remove(pos);
+        if let Some(&moved) = noi.sources.get(pos) {
+            noi.index.insert(moved, pos);
+        }
+
+        Some(noi.sources.is_empty())
+    }
+
+    fn rel_get<'a>(rel: &'a IndexedRelStorage<T>, target: &T::Target) -> Option<&'a T> {
+        rel.index.get(target).map(|&pos| &rel.data[pos])
+    }
+
+    fn rel_get_mut<'a>(rel: &'a mut IndexedRelStorage<T>, target: &T::Target) -> Option<&'a mut T> {
+        rel.index.get(target).map(|&pos| &mut rel.data[pos])
+    }
+
+    fn noi_contains(noi: &IndexedNoiStorage, source: Entity) -> bool {
+        noi.index.contains_key(&source)
+    }
+
+    type RelDataIterMut<'a> = std::slice::IterMut<'a, T>;
+    type RelDataIter<'a> = std::slice::Iter<'a, T>;
+    type RelTargetIter<'a> = std::slice::Iter<'a, T::Target>;
     fn rel_iter_mut(
         rel: &mut Self::RelStorage,
     ) -> (Self::RelDataIterMut<'_>, Self::RelTargetIter<'_>) {
-        (std::iter::once(&mut rel.0), std::iter::once(rel.1))
+        (rel.data.iter_mut(), rel.targets.iter())
     }
     fn rel_iter(rel: &Self::RelStorage) -> (Self::RelDataIter<'_>, Self::RelTargetIter<'_>) {
-        (std::iter::once(&rel.0), std::iter::once(rel.1))
+        (rel.data.iter(), rel.targets.iter())
+    }
+    type RelIntoIter = std::iter::Zip<std::vec::IntoIter<T>, std::vec::IntoIter<T::Target>>;
+    fn rel_into_iter(rel: Self::RelStorage) -> Self::RelIntoIter {
+        rel.data.into_iter().zip(rel.targets)
     }
 
-    type NoiTargetIter<'a> = std::iter::Once<Entity>;
-    fn noi_iter(noi: &Self::NoiStorage) -> Self::NoiTargetIter<'_> {
-        std::iter::once(*noi)
+    type NoiSourceIter<'a> = std::iter::Copied<std::slice::Iter<'a, Entity>>;
+    fn noi_iter(noi: &Self::NoiStorage) -> Self::NoiSourceIter<'_> {
+        noi.sources.iter().copied()
     }
 }
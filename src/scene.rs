@@ -0,0 +1,179 @@
+use bevy::{
+    app::AppTypeRegistry,
+    ecs::{
+        entity::{EntityMap, MapEntities, MapEntitiesError},
+        prelude::*,
+        reflect::{ReflectComponent, ReflectMapEntities},
+    },
+    log::warn,
+    reflect::{FromReflect, FromType, Reflect},
+    scene::DynamicScene,
+};
+
+use crate::{EntityMutExt, Noitaler, RelKind, Relation, RelationEdges, Restriction};
+
+/// Stands in for the relations of kind `T` out of an entity in scenes, since relations are not
+/// components themselves. Inserted by [`dynamic_scene_with_relations`] and
+/// [`relations_into_scene_world`] and turned back into relations by [`load_scene_relations`],
+/// which runs at the start of [`CoreStage::Update`](bevy::app::CoreStage::Update) for kinds
+/// registered with
+/// [`RelationAppExt::register_scene_relation`](crate::app::RelationAppExt::register_scene_relation).
+///
+/// Edges to targets that were not part of the scene are dropped when it is spawned.
+#[derive(Component, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct SceneRelation<T: RelKind<Target = Entity> + FromReflect> {
+    pub targets: Vec<Entity>,
+    /// Data of the relation to the target at the same index
+    pub data: Vec<T>,
+}
+
+impl<T: RelKind<Target = Entity> + FromReflect> Default for SceneRelation<T> {
+    fn default() -> Self {
+        Self {
+            targets: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+}
+
+impl<T: RelKind<Target = Entity> + FromReflect> MapEntities for SceneRelation<T> {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
+        // the old target could be any entity of the world the scene is spawned into
+        let edges = std::mem::take(&mut self.targets)
+            .into_iter()
+            .zip(std::mem::take(&mut self.data))
+            .filter_map(|(target, data)| Some((entity_map.get(target).ok()?, data)));
+        (self.targets, self.data) = edges.unzip();
+        Ok(())
+    }
+}
+
+/// Type data registered alongside [`ReflectRelation`](crate::ReflectRelation) for kinds that
+/// can be saved in scenes
+#[derive(Clone)]
+pub struct ReflectSceneRelation {
+    save: fn(&mut World),
+    unsave: fn(&mut World),
+    strip: fn(&mut World),
+    load: fn(&mut World),
+}
+
+impl<T: RelKind<Target = Entity> + FromReflect> FromType<T> for ReflectSceneRelation {
+    fn from_type() -> Self {
+        ReflectSceneRelation {
+            save: save::<T>,
+            unsave: |world| {
+                let mut query = world.query_filtered::<Entity, With<SceneRelation<T>>>();
+                for entity in query.iter(world).collect::<Vec<_>>() {
+                    world.entity_mut(entity).remove::<SceneRelation<T>>();
+                }
+            },
+            strip: |world| {
+                let mut query = world
+                    .query_filtered::<Entity, Or<(With<RelationEdges<T>>, With<Noitaler<T>>)>>();
+                for entity in query.iter(world).collect::<Vec<_>>() {
+                    // removing the components does not run despawn hooks
+                    world
+                        .entity_mut(entity)
+                        .remove::<(Relation<T>, RelationEdges<T>, Noitaler<T>)>();
+                }
+            },
+            load: load::<T>,
+        }
+    }
+}
+
+fn save<T: RelKind<Target = Entity> + FromReflect>(world: &mut World) {
+    let mut query = world.query::<(Entity, &Relation<T>)>();
+    let saved = query
+        .iter(world)
+        .map(|(source, rel)| {
+            let (data, targets) = T::SourceRestriction::rel_iter(&rel.0);
+            let data = data
+                .map(|data| {
+                    T::from_reflect(data).unwrap_or_else(|| {
+                        panic!(
+                            "`{}` could not be created from a reflected copy of itself",
+                            std::any::type_name::<T>()
+                        )
+                    })
+                })
+                .collect();
+            let targets = targets.copied().collect();
+            (source, SceneRelation { targets, data })
+        })
+        .collect::<Vec<_>>();
+    for (source, saved) in saved {
+        world.entity_mut(source).insert(saved);
+    }
+}
+
+fn load<T: RelKind<Target = Entity> + FromReflect>(world: &mut World) {
+    let mut query = world.query_filtered::<Entity, With<SceneRelation<T>>>();
+    for source in query.iter(world).collect::<Vec<_>>() {
+        let mut source = world.entity_mut(source);
+        let saved = source.remove::<SceneRelation<T>>().unwrap();
+        for (target, data) in saved.targets.into_iter().zip(saved.data) {
+            if let Err(e) = source.try_insert_relation(data, target) {
+                warn!(
+                    "Could not load relation `{:?}` -> {} -> `{:?}`: {}",
+                    source.id(),
+                    std::any::type_name::<T>(),
+                    target,
+                    e
+                );
+            }
+        }
+    }
+}
+
+fn registered(registry: &AppTypeRegistry) -> Vec<ReflectSceneRelation> {
+    registry
+        .read()
+        .iter()
+        .filter_map(|registration| registration.data::<ReflectSceneRelation>())
+        .cloned()
+        .collect()
+}
+
+/// Like [`DynamicScene::from_world`] but also saves the relations of every kind registered with
+/// [`RelationAppExt::register_scene_relation`](crate::app::RelationAppExt::register_scene_relation)
+pub fn dynamic_scene_with_relations(world: &mut World, registry: &AppTypeRegistry) -> DynamicScene {
+    let kinds = registered(registry);
+    for kind in &kinds {
+        (kind.save)(world);
+    }
+    let scene = DynamicScene::from_world(world, registry);
+    for kind in &kinds {
+        (kind.unsave)(world);
+    }
+    scene
+}
+
+/// Replaces the relations of every kind registered with
+/// [`RelationAppExt::register_scene_relation`](crate::app::RelationAppExt::register_scene_relation)
+/// with [`SceneRelation`]s, for worlds that are going to be turned into a
+/// [`Scene`](bevy::scene::Scene). This does not send relation events or apply despawn policies.
+pub fn relations_into_scene_world(world: &mut World, registry: &AppTypeRegistry) {
+    for kind in registered(registry) {
+        (kind.save)(world);
+        (kind.strip)(world);
+    }
+}
+
+/// Turns every [`SceneRelation`] in `world` back into relations, checking restrictions and
+/// cyclicity as if each edge was inserted with
+/// [`EntityMutExt::try_insert_relation`]. Edges that can't be inserted log a warning.
+pub fn load_scene_relations(world: &mut World) {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    for kind in registered(&registry) {
+        (kind.load)(world);
+    }
+}
+
+pub(crate) fn load_scene_relations_system<T: RelKind<Target = Entity> + FromReflect>(
+    world: &mut World,
+) {
+    load::<T>(world);
+}
@@ -0,0 +1,47 @@
+use bevy::prelude::Entity;
+
+use crate::{cyclicity::Cyclic, RelKind, Restriction};
+
+/// Edges only exist in the direction they were inserted in
+pub struct Directed;
+/// Inserting `a -> b` also inserts `b -> a` with a clone of the data, removing either direction
+/// (or despawning either entity) removes both. Both endpoints are restricted by the same
+/// [`Restriction`] which limits how many edges of this kind each entity can be part of.
+///
+/// Relation events are sent for both directions, events for the mirror of an inserted edge do not
+/// carry data. Mutating the data of one direction does not update the other.
+///
+/// Requires the kind to target entities, to be [`Cyclic`] (every edge forms a cycle with its
+/// mirror) and to use the same [`Restriction`] for sources and targets.
+pub struct Symmetric;
+
+pub trait Symmetry<R: RelKind>: crate::sealed::Sealed {
+    #[doc(hidden)]
+    const SYMMETRIC: bool;
+    /// Returns the data for the `target -> source` edge mirroring `data`
+    #[doc(hidden)]
+    fn mirror(data: &R) -> Option<R>;
+}
+
+impl<R: RelKind> Symmetry<R> for Directed {
+    const SYMMETRIC: bool = false;
+    fn mirror(_: &R) -> Option<R> {
+        None
+    }
+}
+
+impl<R, Res> Symmetry<R> for Symmetric
+where
+    R: RelKind<
+            Target = Entity,
+            SourceRestriction = Res,
+            TargetRestriction = Res,
+            Cyclicity = Cyclic,
+        > + Clone,
+    Res: Restriction<R>,
+{
+    const SYMMETRIC: bool = true;
+    fn mirror(data: &R) -> Option<R> {
+        Some(data.clone())
+    }
+}
@@ -0,0 +1,47 @@
+use std::{borrow::Cow, fmt::Debug, hash::Hash};
+
+use bevy::{
+    asset::{Asset, Handle},
+    prelude::Entity,
+};
+
+/// Types that relations can point to, see [`RelKind::Target`](crate::RelKind::Target).
+///
+/// Only `Entity` targets keep track of their sources and are cleaned up when despawned, any
+/// other target is plain data stored on the source. Marker types can opt in with an empty impl:
+/// ```ignore
+/// #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// struct Apples;
+/// impl RelationTarget for Apples {}
+/// ```
+pub trait RelationTarget: Debug + Clone + Eq + Hash + Send + Sync + 'static {
+    #[doc(hidden)]
+    fn as_entity(&self) -> Option<Entity> {
+        None
+    }
+    #[doc(hidden)]
+    fn from_entity(_: Entity) -> Option<Self> {
+        None
+    }
+}
+
+impl RelationTarget for Entity {
+    fn as_entity(&self) -> Option<Entity> {
+        Some(*self)
+    }
+    fn from_entity(entity: Entity) -> Option<Self> {
+        Some(entity)
+    }
+}
+
+impl<A: Asset> RelationTarget for Handle<A> {}
+impl RelationTarget for String {}
+impl RelationTarget for &'static str {}
+impl RelationTarget for Cow<'static, str> {}
+
+macro_rules! impl_relation_target {
+    ($($t:ty),*) => {
+        $(impl RelationTarget for $t {})*
+    };
+}
+impl_relation_target!(bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
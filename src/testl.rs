@@ -1,96 +1,19 @@
-use bevy::ecs::{prelude::*, system::SystemState};
+use bevy::{
+    ecs::{event::Events, prelude::*, system::SystemState},
+    reflect::{FromReflect, Reflect},
+};
 
 use crate::{
+    conformance::assert_relation_graph_good,
     cyclicity::{Acyclic, Cyclic},
+    despawn_policy::{Detach, Recursive, Retarget},
     restriction::{Many, One},
-    EntityMutExt, EntityRefExt, NoitalerRef, RelKind, RelationRef,
+    symmetry::{Directed, Symmetric},
+    AddedRelation, Applied, ChangedNoitaler, ChangedRelation, ChangedRelationData, EntityMutExt,
+    EntityRefExt, RelKind, RelatesTo, RelationError, RelationEvent, RelationTarget, Relations,
+    RelationsMut, RemovedRelations, TargetedBy, TopoOrder, Traverse,
 };
 
-fn assert_relation_graph_good<R: RelKind>(world: &mut World) {
-    let mut state = SystemState::<(
-        Query<(Entity, RelationRef<R>)>,
-        Query<(Entity, NoitalerRef<R>)>,
-    )>::new(world);
-    let (relations, noitalers) = state.get_mut(world);
-
-    for (source, relations) in &relations {
-        for (target, _) in relations {
-            let die = || {
-                panic!(
-                    "entity: {:?} had relation: {} to target: {:?} which did not have an entry in `Noitaler`",
-                    source,
-                    std::any::type_name::<R>(),
-                    target
-                )
-            };
-
-            let (_, noitalers) = noitalers.get(target).unwrap_or_else(|_| die());
-            if noitalers
-                .into_iter()
-                .all(|noitalers_source| noitalers_source != source)
-            {
-                die();
-            }
-        }
-    }
-
-    for (source, relations) in &relations {
-        if relations.into_iter().next().is_none() {
-            panic!(
-                "entity: {:?} had relation: {} with an empty list of targets",
-                source,
-                std::any::type_name::<R>(),
-            );
-        }
-    }
-
-    for (target, noitalers) in &noitalers {
-        for source in noitalers {
-            let die = || {
-                panic!(
-                    "entity: {:?} had noitaler: {} to source: {:?} which did not have an entry in `Relation`",
-                    target,
-                    std::any::type_name::<R>(),
-                    source,
-                )
-            };
-
-            let (_, relations) = relations.get(source).unwrap_or_else(|_| die());
-            if relations
-                .into_iter()
-                .all(|(relations_target, _)| relations_target != target)
-            {
-                die();
-            }
-        }
-    }
-
-    for (target, noitalers) in &noitalers {
-        if noitalers.into_iter().next().is_none() {
-            panic!(
-                "entity: {:?} had noitaler: {} with an empty list of sources",
-                target,
-                std::any::type_name::<R>(),
-            )
-        };
-    }
-
-    let sources = relations
-        .iter()
-        .map(|(source, _)| source)
-        .collect::<Vec<_>>();
-    for source in sources {
-        use crate::AssertTreeIfAcyclic;
-        if R::Cyclicity::assert_cyclicity(world.entity_mut(source)).is_err() {
-            panic!(
-                "entity: {:?} participates in a cycle with relation kind: {} which disallows cycles",
-                source,
-                std::any::type_name::<R>(),
-            );
-        };
-    }
-}
-
 macro_rules! test_world {
     ($($foo:tt)*) => {{
         let mut world = World::new();
@@ -143,7 +66,29 @@ macro_rules! inner_maker {
         $($foo:tt)*
     ) => {
         $(
-            $world.entity_mut($source).remove_relation(R, $target);
+            $world.entity_mut($source).remove_relation::<R>($target);
+            assert_relation_graph_good::<R>(&mut $world);
+        )*
+        inner_maker!{$world; $($foo)*}
+    };
+    (
+        $world:ident;
+        clear_relations: [$($entity:ident),*]
+        $($foo:tt)*
+    ) => {
+        $(
+            $world.entity_mut($entity).clear_relations::<R>();
+            assert_relation_graph_good::<R>(&mut $world);
+        )*
+        inner_maker!{$world; $($foo)*}
+    };
+    (
+        $world:ident;
+        clear_noitalers: [$($entity:ident),*]
+        $($foo:tt)*
+    ) => {
+        $(
+            $world.entity_mut($entity).clear_noitalers::<R>();
             assert_relation_graph_good::<R>(&mut $world);
         )*
         inner_maker!{$world; $($foo)*}
@@ -211,9 +156,13 @@ macro_rules! inner_maker {
 fn cycle_despawn() {
     struct R;
     impl RelKind for R {
+        type Target = Entity;
         type SourceRestriction = One;
         type TargetRestriction = One;
         type Cyclicity = Cyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
     }
 
     test_world! {
@@ -239,9 +188,13 @@ fn cycle_despawn() {
 fn target_restriction() {
     struct R;
     impl RelKind for R {
+        type Target = Entity;
         type SourceRestriction = Many;
         type TargetRestriction = One;
         type Cyclicity = Cyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
     }
 
     test_world! {
@@ -268,9 +221,13 @@ fn target_restriction() {
 fn source_restriction() {
     struct R;
     impl RelKind for R {
+        type Target = Entity;
         type SourceRestriction = One;
         type TargetRestriction = Many;
         type Cyclicity = Cyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
     }
 
     test_world! {
@@ -297,9 +254,13 @@ fn source_restriction() {
 fn despawning_removes_noitaler() {
     struct R;
     impl RelKind for R {
+        type Target = Entity;
         type SourceRestriction = One;
         type TargetRestriction = Many;
         type Cyclicity = Cyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
     }
 
     test_world! {
@@ -332,9 +293,13 @@ fn despawning_removes_noitaler() {
 fn self_cycle() {
     struct R;
     impl RelKind for R {
+        type Target = Entity;
         type SourceRestriction = One;
         type TargetRestriction = One;
         type Cyclicity = Acyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
     }
 
     test_world! {
@@ -351,9 +316,13 @@ fn self_cycle() {
 fn simple_cycle() {
     struct R;
     impl RelKind for R {
+        type Target = Entity;
         type SourceRestriction = One;
         type TargetRestriction = One;
         type Cyclicity = Acyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
     }
 
     test_world! {
@@ -371,9 +340,13 @@ fn simple_cycle() {
 fn complex_cycle() {
     struct R;
     impl RelKind for R {
+        type Target = Entity;
         type SourceRestriction = Many;
         type TargetRestriction = One;
         type Cyclicity = Acyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
     }
 
     test_world! {
@@ -393,9 +366,13 @@ fn complex_cycle() {
 fn has_multiple_relations() {
     struct R;
     impl RelKind for R {
+        type Target = Entity;
         type SourceRestriction = Many;
         type TargetRestriction = One;
         type Cyclicity = Acyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
     }
 
     test_world! {
@@ -412,3 +389,1944 @@ fn has_multiple_relations() {
         }
     };
 }
+
+#[test]
+fn clear_relations() {
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many;
+        type TargetRestriction = Many;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    test_world! {
+        spawn: [e0, e1, e2, e3]
+
+        insert: {
+            e0->e1,
+            e0->e2,
+            e0->e0,
+            e1->e0,
+            e3->e1,
+        }
+
+        clear_relations: [e0]
+
+        not_exists: {
+            e0->e1,
+            e0->e2,
+            e0->e0,
+        }
+
+        exists: {
+            e1->e0,
+            e3->e1,
+        }
+
+        clear_noitalers: [e1]
+        alive: [e0, e1, e2, e3]
+
+        not_exists: {
+            e3->e1,
+        }
+
+        exists: {
+            e1->e0,
+        }
+    };
+}
+
+#[test]
+fn detach_despawn() {
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = Many;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    test_world! {
+        spawn: [e0, e1, e2, e3]
+
+        insert: {
+            e0->e2,
+            e1->e2,
+            e2->e3,
+        }
+
+        despawn: [e0]
+        dead: [e0]
+        alive: [e1, e2, e3]
+
+        exists: {
+            e1->e2,
+            e2->e3,
+        }
+
+        despawn: [e3]
+        dead: [e3]
+        alive: [e1, e2]
+
+        not_exists: {
+            e2->e3,
+        }
+    };
+}
+
+#[test]
+fn cascade_target_despawn() {
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many;
+        type TargetRestriction = Many;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Recursive;
+        type Symmetry = Directed;
+    }
+
+    test_world! {
+        spawn: [e0, e1, e2, e3]
+
+        insert: {
+            e1->e0,
+            e2->e1,
+            e1->e3,
+        }
+
+        despawn: [e0]
+        dead: [e0, e1, e2]
+        alive: [e3]
+    };
+}
+
+#[test]
+fn retarget_target_despawn() {
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = Many;
+        type Cyclicity = Acyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Retarget;
+        type Symmetry = Directed;
+    }
+
+    test_world! {
+        spawn: [e0, e1, e2, e3, e4]
+
+        insert: {
+            e1->e0,
+            e2->e1,
+            e3->e1,
+            e4->e3,
+        }
+
+        despawn: [e1]
+        dead: [e1]
+        alive: [e0, e2, e3, e4]
+
+        exists: {
+            e2->e0,
+            e3->e0,
+            e4->e3,
+        }
+
+        despawn: [e0]
+        dead: [e0]
+        alive: [e2, e3, e4]
+
+        not_exists: {
+            e2->e0,
+            e3->e0,
+        }
+    };
+}
+
+#[test]
+fn change_detection() {
+    struct R(u8);
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many;
+        type TargetRestriction = Many;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    fn changed<F: bevy::ecs::query::ReadOnlyWorldQuery>(world: &mut World) -> Vec<Entity> {
+        world.query_filtered::<Entity, F>().iter(world).collect()
+    }
+
+    let mut world = World::new();
+    let [e0, e1, e2] = [(); 3].map(|_| world.spawn(()).id());
+
+    world.entity_mut(e0).insert_relation(R(0), e1);
+    assert_eq!(changed::<AddedRelation<R>>(&mut world), [e0]);
+    assert_eq!(changed::<ChangedRelation<R>>(&mut world), [e0]);
+    assert_eq!(changed::<ChangedNoitaler<R>>(&mut world), [e1]);
+
+    world.clear_trackers();
+    world.entity_mut(e0).insert_relation(R(1), e2);
+    assert_eq!(changed::<AddedRelation<R>>(&mut world), []);
+    assert_eq!(changed::<ChangedRelation<R>>(&mut world), [e0]);
+    assert_eq!(changed::<ChangedNoitaler<R>>(&mut world), [e2]);
+
+    world.clear_trackers();
+    world.entity_mut(e0).get_relation_mut::<R>(e1).unwrap().0 = 2;
+    assert_eq!(changed::<ChangedRelation<R>>(&mut world), []);
+    assert_eq!(changed::<ChangedRelationData<R>>(&mut world), [e0]);
+
+    world.clear_trackers();
+    world.entity_mut(e0).remove_relation::<R>(e2);
+    assert_eq!(changed::<ChangedRelation<R>>(&mut world), [e0]);
+    assert_eq!(changed::<ChangedRelationData<R>>(&mut world), []);
+    assert_eq!(changed::<ChangedNoitaler<R>>(&mut world), []);
+}
+
+#[test]
+fn removed_relations() {
+    struct R(u8);
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = Many;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    world.init_resource::<Events<RelationEvent<R>>>();
+    let mut state = SystemState::<RemovedRelations<R>>::new(&mut world);
+    let [e0, e1, e2] = [(); 3].map(|_| world.spawn(()).id());
+
+    world.entity_mut(e0).insert_relation(R(0), e1);
+    world.entity_mut(e0).insert_relation(R(1), e2);
+    world.entity_mut(e0).remove_relation::<R>(e2);
+    world.entity_mut(e1).insert_relation(R(2), e2);
+    world.despawn(e2);
+
+    let removed = state
+        .get_mut(&mut world)
+        .iter()
+        .map(|(source, target, data)| (source, target, data.map(|data| data.0)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        removed,
+        [(e0, e1, Some(0)), (e0, e2, Some(1)), (e1, e2, Some(2))]
+    );
+    assert_eq!(state.get_mut(&mut world).iter().count(), 0);
+}
+
+#[test]
+fn relation_events() {
+    #[derive(Debug, Clone, PartialEq)]
+    struct R(u8);
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = One;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    world.init_resource::<Events<RelationEvent<R>>>();
+    let mut state = SystemState::<EventReader<RelationEvent<R>>>::new(&mut world);
+    let [e0, e1, e2] = [(); 3].map(|_| world.spawn(()).id());
+
+    world.entity_mut(e0).insert_relation(R(0), e1);
+    world.entity_mut(e0).insert_relation(R(1), e1);
+    world.entity_mut(e2).insert_relation(R(2), e1);
+    world.entity_mut(e2).try_insert_relation(R(3), e0).unwrap();
+    world.entity_mut(e2).remove_relation::<R>(e0);
+
+    let events = state
+        .get_mut(&mut world)
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        events,
+        [
+            RelationEvent::Inserted {
+                source: e0,
+                target: e1
+            },
+            RelationEvent::DataReplaced {
+                source: e0,
+                target: e1,
+                old: Some(R(0))
+            },
+            RelationEvent::Evicted {
+                source: e0,
+                target: e1,
+                data: Some(R(1))
+            },
+            RelationEvent::Inserted {
+                source: e2,
+                target: e1
+            },
+            RelationEvent::Evicted {
+                source: e2,
+                target: e1,
+                data: None
+            },
+            RelationEvent::Inserted {
+                source: e2,
+                target: e0
+            },
+            RelationEvent::Removed {
+                source: e2,
+                target: e0,
+                data: Some(R(3))
+            },
+        ]
+    );
+}
+
+#[test]
+fn try_insert_errors() {
+    #[derive(Debug)]
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = Many;
+        type Cyclicity = Acyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [e0, e1, e2, e3] = [(); 4].map(|_| world.spawn(()).id());
+    inner_maker! {
+        world;
+        insert: {
+            e1->e0,
+            e2->e1,
+            e3->e2,
+        }
+    }
+
+    let dead = world.spawn(()).id();
+    world.despawn(dead);
+    assert_eq!(
+        world
+            .entity_mut(e0)
+            .try_insert_relation(R, dead)
+            .unwrap_err(),
+        RelationError::DeadTarget(dead),
+    );
+    assert_eq!(
+        world.entity_mut(e0).try_insert_relation(R, e0).unwrap_err(),
+        RelationError::SelfEdge(e0),
+    );
+    assert_eq!(
+        world.entity_mut(e1).try_insert_relation(R, e3).unwrap_err(),
+        RelationError::Cycle(vec![e1, e3, e2]),
+    );
+
+    inner_maker! {
+        world;
+        exists: {
+            e1->e0,
+            e2->e1,
+            e3->e2,
+        }
+    }
+}
+
+#[test]
+fn try_insert_outcome() {
+    #[derive(Debug, PartialEq)]
+    struct R(u8);
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = One;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [e0, e1, e2] = [(); 3].map(|_| world.spawn(()).id());
+
+    world.entity_mut(e0).insert_relation(R(0), e1);
+    world.entity_mut(e2).insert_relation(R(1), e0);
+
+    let outcome = world.entity_mut(e0).try_insert_relation(R(2), e1).unwrap();
+    assert_eq!(outcome.replaced, Some(R(0)));
+
+    let outcome = world.entity_mut(e2).try_insert_relation(R(3), e1).unwrap();
+    assert_eq!(outcome.replaced, None);
+    assert_eq!(outcome.evicted_target, Some((e0, R(1))));
+    assert_eq!(outcome.evicted_source, Some((e0, R(2))));
+    assert_relation_graph_good::<R>(&mut world);
+}
+
+#[test]
+fn non_entity_targets() {
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum Fruit {
+        Apples,
+        Pears,
+    }
+    impl RelationTarget for Fruit {}
+
+    #[derive(Debug, PartialEq)]
+    struct Likes(u8);
+    impl RelKind for Likes {
+        type Target = Fruit;
+        type SourceRestriction = One;
+        type TargetRestriction = Many;
+        type Cyclicity = Acyclic;
+        type DespawnPolicy = Recursive;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    world.init_resource::<Events<RelationEvent<Likes>>>();
+    let [e0, e1] = [(); 2].map(|_| world.spawn(()).id());
+
+    world
+        .entity_mut(e0)
+        .insert_relation(Likes(0), Fruit::Apples);
+    world
+        .entity_mut(e1)
+        .insert_relation(Likes(1), Fruit::Apples);
+    assert_eq!(
+        world.entity(e0).get_relation::<Likes>(Fruit::Apples),
+        Some(&Likes(0))
+    );
+    assert_eq!(world.entity(e0).get_relation::<Likes>(Fruit::Pears), None);
+
+    let outcome = world
+        .entity_mut(e0)
+        .try_insert_relation(Likes(2), Fruit::Pears)
+        .unwrap();
+    assert_eq!(outcome.evicted_target, Some((Fruit::Apples, Likes(0))));
+    assert_eq!(outcome.evicted_source, None);
+    assert_eq!(
+        world
+            .entity(e0)
+            .get_all_relations::<Likes>()
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>(),
+        [(Fruit::Pears, &Likes(2))]
+    );
+
+    world.entity_mut(e1).remove_relation::<Likes>(Fruit::Apples);
+    assert!(world.entity(e1).get_all_relations::<Likes>().is_none());
+
+    world.despawn(e0);
+    let mut state = SystemState::<RemovedRelations<Likes>>::new(&mut world);
+    let mut removed = state.get_mut(&mut world);
+    assert_eq!(
+        removed.iter().collect::<Vec<_>>(),
+        [
+            (e0, Fruit::Apples, None),
+            (e1, Fruit::Apples, Some(&Likes(1))),
+            (e0, Fruit::Pears, Some(&Likes(2))),
+        ]
+    );
+}
+
+#[test]
+fn relates_to_and_targeted_by() {
+    #[derive(Component, Debug, PartialEq)]
+    struct Name(u8);
+
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many;
+        type TargetRestriction = Many;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [e0, e1, e2, e3] = [0, 1, 2, 3].map(|name| world.spawn(Name(name)).id());
+    inner_maker! {
+        world;
+        insert: {
+            e0->e2,
+            e1->e2,
+            e0->e3,
+        }
+    }
+    world.entity_mut(e1).remove::<Name>();
+
+    let mut state = SystemState::<RelatesTo<R, &mut Name>>::new(&mut world);
+    let mut relates_to = state.get_mut(&mut world);
+    assert_eq!(relates_to.iter(e2).collect::<Vec<_>>(), [&Name(0)]);
+    assert_eq!(relates_to.iter(e0).count(), 0);
+    assert!(relates_to.contains(e0, e3));
+    assert!(!relates_to.contains(e1, e2));
+    relates_to.for_each_mut(e3, |mut name| name.0 = 10);
+
+    let mut state = SystemState::<TargetedBy<R, &Name>>::new(&mut world);
+    let targeted_by = state.get(&world);
+    assert_eq!(
+        targeted_by.iter(e0).collect::<Vec<_>>(),
+        [&Name(2), &Name(3)]
+    );
+    assert_eq!(targeted_by.iter(e2).count(), 0);
+    assert!(targeted_by.contains(e2, e1));
+    assert_eq!(world.get::<Name>(e0), Some(&Name(10)));
+}
+
+#[test]
+fn many_many_acyclic() {
+    #[derive(Debug)]
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many;
+        type TargetRestriction = Many;
+        type Cyclicity = Acyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [e0, e1, e2, e3] = [(); 4].map(|_| world.spawn(()).id());
+    inner_maker! {
+        world;
+        insert: {
+            e0->e1,
+            e0->e2,
+            e1->e3,
+            e2->e3,
+        }
+    }
+
+    assert_eq!(
+        world.entity_mut(e3).try_insert_relation(R, e0).unwrap_err(),
+        RelationError::Cycle(vec![e3, e0, e2]),
+    );
+    assert_eq!(
+        world.entity_mut(e3).try_insert_relation(R, e3).unwrap_err(),
+        RelationError::SelfEdge(e3),
+    );
+    world.entity_mut(e1).try_insert_relation(R, e2).unwrap();
+    assert_relation_graph_good::<R>(&mut world);
+}
+
+#[test]
+fn traversal() {
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = Many;
+        type Cyclicity = Acyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [e0, e1, e2, e3, e4] = [(); 5].map(|_| world.spawn(()).id());
+    inner_maker! {
+        world;
+        insert: {
+            e1->e0,
+            e2->e0,
+            e3->e1,
+            e4->e1,
+        }
+    }
+
+    let entity = world.entity(e3);
+    assert_eq!(entity.ancestors::<R>().collect::<Vec<_>>(), [e1, e0]);
+    assert_eq!(entity.root::<R>(), e0);
+    assert_eq!(entity.siblings::<R>().collect::<Vec<_>>(), [e4]);
+
+    let entity = world.entity(e0);
+    assert_eq!(entity.root::<R>(), e0);
+    assert_eq!(entity.siblings::<R>().count(), 0);
+    assert_eq!(
+        entity.descendants_bfs::<R>().collect::<Vec<_>>(),
+        [e1, e2, e3, e4]
+    );
+    assert_eq!(
+        entity.descendants_dfs::<R>().collect::<Vec<_>>(),
+        [e1, e3, e4, e2]
+    );
+    assert_eq!(entity.leaves::<R>().collect::<Vec<_>>(), [e3, e4, e2]);
+
+    let mut state = SystemState::<Relations<R>>::new(&mut world);
+    let relations = state.get(&world);
+    assert_eq!(relations.ancestors(e4).collect::<Vec<_>>(), [e1, e0]);
+    assert_eq!(relations.descendants_bfs(e1).collect::<Vec<_>>(), [e3, e4]);
+    assert_eq!(relations.root(e2), e0);
+}
+
+#[test]
+fn cyclic_traversal_terminates() {
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = Many;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [e0, e1, e2] = [(); 3].map(|_| world.spawn(()).id());
+    inner_maker! {
+        world;
+        insert: {
+            e0->e1,
+            e1->e2,
+            e2->e0,
+        }
+    }
+
+    let entity = world.entity(e0);
+    assert_eq!(entity.ancestors::<R>().collect::<Vec<_>>(), [e1, e2]);
+    assert_eq!(entity.root::<R>(), e2);
+    assert_eq!(entity.descendants_dfs::<R>().collect::<Vec<_>>(), [e2, e1]);
+    assert_eq!(entity.leaves::<R>().count(), 0);
+}
+
+#[test]
+fn relations_param() {
+    #[derive(Debug, PartialEq)]
+    struct R(u8);
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many;
+        type TargetRestriction = Many;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [e0, e1, e2] = [(); 3].map(|_| world.spawn(()).id());
+    world.entity_mut(e0).insert_relation(R(0), e1);
+    world.entity_mut(e0).insert_relation(R(1), e2);
+    world.entity_mut(e1).insert_relation(R(2), e2);
+
+    let mut state = SystemState::<Relations<R>>::new(&mut world);
+    let relations = state.get(&world);
+    assert_eq!(relations.targets(e0).collect::<Vec<_>>(), [e1, e2]);
+    assert_eq!(relations.targets(e2).count(), 0);
+    assert_eq!(relations.sources(e2).collect::<Vec<_>>(), [e0, e1]);
+    assert_eq!(relations.sources(e0).count(), 0);
+    assert_eq!(relations.data(e1, e2), Some(&R(2)));
+    assert_eq!(relations.data(e2, e1), None);
+    assert!(relations.has(e0, e1));
+    assert!(!relations.has(e1, e0));
+}
+
+#[test]
+fn relations_mut_param() {
+    #[derive(Debug, Clone, PartialEq)]
+    struct R(u8);
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = Many;
+        type Cyclicity = Acyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    world.init_resource::<Events<RelationEvent<R>>>();
+    let [e0, e1, e2, e3, e4] = [(); 5].map(|_| world.spawn(()).id());
+    world.entity_mut(e0).insert_relation(R(0), e2);
+    world.entity_mut(e1).insert_relation(R(1), e2);
+    world.entity_mut(e3).insert_relation(R(2), e1);
+
+    let mut state = SystemState::<RelationsMut<R>>::new(&mut world);
+    let mut relations = state.get_mut(&mut world);
+    let outcome = match relations.insert(e0, R(3), e1) {
+        Ok(Applied::Immediately(outcome)) => outcome,
+        applied => panic!("unexpected result: {:?}", applied),
+    };
+    assert_eq!(outcome.evicted_target, Some((e2, R(0))));
+    assert_eq!(relations.data(e0, e1), Some(&R(3)));
+    assert!(!relations.has(e0, e2));
+    assert_eq!(relations.sources(e1).collect::<Vec<_>>(), [e3, e0]);
+    assert_eq!(relations.sources(e2).collect::<Vec<_>>(), [e1]);
+    relations.data_mut(e0, e1).unwrap().0 = 4;
+
+    // `e0` has no sources so this would be deferred, errors are still reported right away
+    assert_eq!(
+        relations.insert(e1, R(5), e0).unwrap_err(),
+        RelationError::Cycle(vec![e1, e0])
+    );
+    // `e1` and `e2` keep their now empty storages until commands are applied
+    assert!(relations.remove(e1, e2));
+    assert!(!relations.remove(e1, e3));
+    assert_eq!(relations.targets(e1).count(), 0);
+    assert_eq!(relations.sources(e2).count(), 0);
+    // first edges need components to be inserted so they are only visible after commands are
+    // applied
+    assert!(matches!(
+        relations.insert(e2, R(6), e0),
+        Ok(Applied::Deferred)
+    ));
+    assert!(matches!(
+        relations.insert(e1, R(7), e4),
+        Ok(Applied::Deferred)
+    ));
+    assert!(!relations.has(e2, e0) && !relations.has(e1, e4));
+    state.apply(&mut world);
+    assert!(world.entity(e2).get_all_noitalers::<R>().is_none());
+
+    inner_maker! {
+        world;
+        exists: {
+            e0->e1,
+            e3->e1,
+            e2->e0,
+            e1->e4,
+        }
+        not_exists: {
+            e0->e2,
+            e1->e2,
+        }
+    }
+    assert_eq!(world.entity(e0).get_relation::<R>(e1), Some(&R(4)));
+
+    let events = world.resource::<Events<RelationEvent<R>>>();
+    let mut reader = events.get_reader();
+    assert_eq!(
+        reader.iter(events).skip(3).cloned().collect::<Vec<_>>(),
+        [
+            RelationEvent::Evicted {
+                source: e0,
+                target: e2,
+                data: None
+            },
+            RelationEvent::Inserted {
+                source: e0,
+                target: e1
+            },
+            RelationEvent::Removed {
+                source: e1,
+                target: e2,
+                data: Some(R(1))
+            },
+            RelationEvent::Inserted {
+                source: e2,
+                target: e0
+            },
+            RelationEvent::Inserted {
+                source: e1,
+                target: e4
+            },
+        ]
+    );
+}
+
+#[test]
+fn relations_mut_symmetric() {
+    #[derive(Debug, Clone, PartialEq)]
+    struct MarriedTo(u8);
+    impl RelKind for MarriedTo {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = One;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Symmetric;
+    }
+
+    let mut world = World::new();
+    let [e0, e1, e2, e3] = [(); 4].map(|_| world.spawn(()).id());
+    world.entity_mut(e0).insert_relation(MarriedTo(0), e1);
+    world.entity_mut(e2).insert_relation(MarriedTo(1), e3);
+
+    let mut state = SystemState::<RelationsMut<MarriedTo>>::new(&mut world);
+    let mut relations = state.get_mut(&mut world);
+    let outcome = match relations.insert(e0, MarriedTo(2), e3) {
+        Ok(Applied::Immediately(outcome)) => outcome,
+        applied => panic!("unexpected result: {:?}", applied),
+    };
+    assert_eq!(outcome.evicted_target, Some((e1, MarriedTo(0))));
+    assert_eq!(outcome.evicted_source, Some((e2, MarriedTo(1))));
+    assert_eq!(relations.data(e3, e0), Some(&MarriedTo(2)));
+    for e in [e1, e2] {
+        assert_eq!(relations.targets(e).count(), 0);
+        assert_eq!(relations.sources(e).count(), 0);
+    }
+    state.apply(&mut world);
+    assert_relation_graph_good::<MarriedTo>(&mut world);
+    for e in [e1, e2] {
+        assert!(world.entity(e).get_all_relations::<MarriedTo>().is_none());
+        assert!(world.entity(e).get_all_noitalers::<MarriedTo>().is_none());
+    }
+
+    let mut relations = state.get_mut(&mut world);
+    assert!(relations.remove(e3, e0));
+    assert!(!relations.has(e0, e3));
+    state.apply(&mut world);
+    assert_relation_graph_good::<MarriedTo>(&mut world);
+    assert!(world.entity(e0).get_all_relations::<MarriedTo>().is_none());
+}
+
+#[test]
+fn topo_order() {
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many;
+        type TargetRestriction = Many;
+        type Cyclicity = Acyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [e0, e1, e2, e3, e4, e5] = [(); 6].map(|_| world.spawn(()).id());
+    inner_maker! {
+        world;
+        insert: {
+            e1->e0,
+            e2->e0,
+            e3->e1,
+            e3->e2,
+            e4->e0,
+            e4->e3,
+        }
+    }
+
+    let mut state = SystemState::<TopoOrder<R>>::new(&mut world);
+    let topo = state.get(&world);
+    let mut levels = topo.levels();
+    levels.iter_mut().for_each(|level| level.sort());
+    assert_eq!(levels, [vec![e0], vec![e1, e2], vec![e3], vec![e4]]);
+    assert_eq!(topo.iter().count(), 5);
+    assert!(!topo.iter().any(|entity| entity == e5));
+
+    // `e1` keeps an empty storage until commands are applied, it is a root until then
+    let mut relations = SystemState::<RelationsMut<R>>::new(&mut world);
+    assert!(relations.get_mut(&mut world).remove(e1, e0));
+    let topo = state.get(&world);
+    let mut levels = topo.levels();
+    levels.iter_mut().for_each(|level| level.sort());
+    assert_eq!(levels, [vec![e0, e1], vec![e2], vec![e3], vec![e4]]);
+}
+
+#[test]
+fn propagate_plugin() {
+    use crate::{Propagate, PropagatePlugin};
+    use bevy::app::App;
+
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = Many;
+        type Cyclicity = Acyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    #[derive(Component)]
+    struct Level(u32);
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct TotalLevel(u32);
+    impl Propagate for Level {
+        type Out = TotalLevel;
+        fn root(local: &Level) -> TotalLevel {
+            TotalLevel(local.0)
+        }
+        fn propagate(parent: &TotalLevel, local: &Level) -> TotalLevel {
+            TotalLevel(parent.0 + local.0)
+        }
+    }
+
+    let mut app = App::new();
+    app.add_plugin(PropagatePlugin::<R, Level>::default());
+    let world = &mut app.world;
+    let [e0, e1, e2, e3] =
+        [1, 10, 100, 1000].map(|level| world.spawn((Level(level), TotalLevel(0))).id());
+    world.entity_mut(e1).insert_relation(R, e0);
+    world.entity_mut(e2).insert_relation(R, e1);
+    world.entity_mut(e3).insert_relation(R, e0);
+
+    let total = |app: &App, e| app.world.get::<TotalLevel>(e).unwrap().0;
+    app.update();
+    assert_eq!([e0, e1, e2, e3].map(|e| total(&app, e)), [1, 11, 111, 1001]);
+
+    // nothing changed so the stale value is left alone
+    app.world.get_mut::<TotalLevel>(e2).unwrap().0 = 0;
+    app.update();
+    assert_eq!(total(&app, e2), 0);
+
+    app.world.get_mut::<Level>(e1).unwrap().0 = 20;
+    app.update();
+    assert_eq!([e0, e1, e2, e3].map(|e| total(&app, e)), [1, 21, 121, 1001]);
+
+    app.world.entity_mut(e2).insert_relation(R, e3);
+    app.update();
+    assert_eq!(total(&app, e2), 1101);
+
+    // only the subtree below the change is visited, it builds on the stale value of `e0`
+    app.world.get_mut::<TotalLevel>(e0).unwrap().0 = 0;
+    app.world.get_mut::<Level>(e3).unwrap().0 = 2000;
+    app.update();
+    assert_eq!(
+        [e0, e1, e3, e2].map(|e| total(&app, e)),
+        [0, 21, 2000, 2100]
+    );
+
+    app.world.entity_mut(e2).remove_relation::<R>(e3);
+    app.update();
+    assert_eq!(total(&app, e2), 100);
+}
+
+#[test]
+fn aggregate_plugin() {
+    use crate::{
+        aggregate::{Count, Max, Sum},
+        AggregatePlugin, AggregateSource, AggregateTarget,
+    };
+    use bevy::app::App;
+
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = Many;
+        type Cyclicity = Acyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    #[derive(Component)]
+    struct Strength(u32);
+    impl AggregateSource for Strength {
+        type Value = u32;
+        fn value(&self) -> u32 {
+            self.0
+        }
+    }
+    #[derive(Component, Default)]
+    struct Total(u32);
+    impl AggregateTarget<u32> for Total {
+        fn set(&mut self, value: u32) {
+            self.0 = value;
+        }
+    }
+    #[derive(Component, Default)]
+    struct Strongest(u32);
+    impl AggregateTarget<u32> for Strongest {
+        fn set(&mut self, value: u32) {
+            self.0 = value;
+        }
+    }
+    #[derive(Component, Default)]
+    struct Members(usize);
+    impl AggregateTarget<usize> for Members {
+        fn set(&mut self, value: usize) {
+            self.0 = value;
+        }
+    }
+
+    let mut app = App::new();
+    app.add_plugin(AggregatePlugin::<R, Strength, Total, Sum>::default())
+        .add_plugin(AggregatePlugin::<R, Strength, Strongest, Max>::default())
+        .add_plugin(AggregatePlugin::<R, Strength, Members, Count>::default());
+    let world = &mut app.world;
+    let group = world
+        .spawn((Total::default(), Strongest::default(), Members::default()))
+        .id();
+    let [e0, e1, e2] = [3, 5, 1].map(|strength| world.spawn(Strength(strength)).id());
+    for e in [e0, e1, e2] {
+        world.entity_mut(e).insert_relation(R, group);
+    }
+
+    let stats = |app: &App| {
+        let entity = app.world.entity(group);
+        (
+            entity.get::<Total>().unwrap().0,
+            entity.get::<Strongest>().unwrap().0,
+            entity.get::<Members>().unwrap().0,
+        )
+    };
+    app.update();
+    assert_eq!(stats(&app), (9, 5, 3));
+
+    app.world.get_mut::<Strength>(e2).unwrap().0 = 10;
+    app.update();
+    assert_eq!(stats(&app), (18, 10, 3));
+
+    app.world.entity_mut(e1).remove_relation::<R>(group);
+    app.world.entity_mut(e2).remove::<Strength>();
+    app.update();
+    assert_eq!(stats(&app), (3, 3, 1));
+
+    app.world.entity_mut(e0).remove_relation::<R>(group);
+    app.update();
+    assert_eq!(stats(&app), (0, 3, 0));
+}
+
+#[test]
+fn hierarchy_mirror() {
+    use crate::{migrate_hierarchy, ChildOf, HierarchyMirrorPlugin};
+    use bevy::{
+        app::App,
+        hierarchy::{BuildWorldChildren, Children, Parent},
+    };
+
+    let mut app = App::new();
+    let world = &mut app.world;
+    let [p0, p1, c0, c1] = [(); 4].map(|_| world.spawn_empty().id());
+    world.entity_mut(p0).push_children(&[c0]);
+    migrate_hierarchy(world);
+    assert!(world.entity(c0).get_relation::<ChildOf>(p0).is_some());
+
+    app.add_plugin(HierarchyMirrorPlugin);
+    let parent = |app: &App, e| app.world.get::<Parent>(e).map(Parent::get);
+    let children = |app: &App, e| {
+        app.world
+            .get::<Children>(e)
+            .map_or(Vec::new(), |children| children.to_vec())
+    };
+
+    app.world.entity_mut(c1).insert_relation(ChildOf, p0);
+    app.update();
+    assert_eq!(parent(&app, c1), Some(p0));
+    assert_eq!(children(&app, p0), [c0, c1]);
+
+    app.world.entity_mut(p1).push_children(&[c0]);
+    app.update();
+    assert!(app.world.entity(c0).get_relation::<ChildOf>(p1).is_some());
+    assert_eq!(children(&app, p0), [c1]);
+
+    app.world.entity_mut(p1).remove_children(&[c0]);
+    app.update();
+    assert!(app.world.entity(c0).get_relation::<ChildOf>(p1).is_none());
+
+    app.world.entity_mut(c1).remove_relation::<ChildOf>(p0);
+    app.update();
+    assert_eq!(parent(&app, c1), None);
+    assert_eq!(children(&app, p0), []);
+
+    // the hierarchy wins over conflicting edits to relations
+    app.world.entity_mut(c1).insert_relation(ChildOf, p0);
+    app.world.entity_mut(p1).push_children(&[c1]);
+    app.update();
+    app.update();
+    assert_eq!(parent(&app, c1), Some(p1));
+    assert!(app.world.entity(c1).get_relation::<ChildOf>(p1).is_some());
+
+    app.world.entity_mut(p1).push_children(&[c0]);
+    app.update();
+    app.world.despawn(c1);
+    app.update();
+    assert_eq!(children(&app, p1), [c0]);
+    assert_eq!(parent(&app, c0), Some(p1));
+
+    app.world.despawn(p1);
+    assert!(app.world.get_entity(c0).is_none());
+    assert_relation_graph_good::<ChildOf>(&mut app.world);
+}
+
+#[test]
+fn symmetric_relations() {
+    #[derive(Debug, Clone, PartialEq)]
+    struct MarriedTo(u8);
+    impl RelKind for MarriedTo {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = One;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Symmetric;
+    }
+
+    #[derive(Clone)]
+    struct FriendsWith;
+    impl RelKind for FriendsWith {
+        type Target = Entity;
+        type SourceRestriction = Many;
+        type TargetRestriction = Many;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Symmetric;
+    }
+
+    let mut world = World::new();
+    world.init_resource::<Events<RelationEvent<MarriedTo>>>();
+    let mut state = SystemState::<EventReader<RelationEvent<MarriedTo>>>::new(&mut world);
+    let [e0, e1, e2, e3] = [(); 4].map(|_| world.spawn(()).id());
+
+    world.entity_mut(e0).insert_relation(MarriedTo(0), e1);
+    world.entity_mut(e2).insert_relation(MarriedTo(1), e3);
+    assert_eq!(
+        world.entity(e1).get_relation::<MarriedTo>(e0),
+        Some(&MarriedTo(0))
+    );
+    assert_eq!(
+        world.entity(e3).get_relation::<MarriedTo>(e2),
+        Some(&MarriedTo(1))
+    );
+    state.get_mut(&mut world).iter().for_each(drop);
+
+    // both endpoints are restricted so this evicts `e0 <-> e1` and `e2 <-> e3`
+    let outcome = world
+        .entity_mut(e0)
+        .try_insert_relation(MarriedTo(2), e3)
+        .unwrap();
+    assert_eq!(outcome.evicted_target, Some((e1, MarriedTo(0))));
+    assert_eq!(outcome.evicted_source, Some((e2, MarriedTo(1))));
+    assert_eq!(
+        world.entity(e3).get_relation::<MarriedTo>(e0),
+        Some(&MarriedTo(2))
+    );
+    for e in [e1, e2] {
+        assert!(world.entity(e).get_all_relations::<MarriedTo>().is_none());
+        assert!(world.entity(e).get_all_noitalers::<MarriedTo>().is_none());
+    }
+    let events = state
+        .get_mut(&mut world)
+        .iter()
+        .map(|event| match event {
+            RelationEvent::Inserted { source, target } => ("inserted", *source, *target),
+            RelationEvent::Evicted { source, target, .. } => ("evicted", *source, *target),
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        events,
+        [
+            ("evicted", e0, e1),
+            ("evicted", e2, e3),
+            ("inserted", e0, e3),
+            ("evicted", e1, e0),
+            ("evicted", e3, e2),
+            ("inserted", e3, e0),
+        ]
+    );
+
+    world.entity_mut(e3).remove_relation::<MarriedTo>(e0);
+    assert!(world.entity(e0).get_all_relations::<MarriedTo>().is_none());
+    assert!(world.entity(e0).get_all_noitalers::<MarriedTo>().is_none());
+    assert_relation_graph_good::<MarriedTo>(&mut world);
+
+    world.entity_mut(e0).insert_relation(FriendsWith, e1);
+    world.entity_mut(e0).insert_relation(FriendsWith, e2);
+    world.entity_mut(e3).insert_relation(FriendsWith, e0);
+    world.entity_mut(e2).insert_relation(FriendsWith, e2);
+    assert_eq!(
+        world
+            .entity(e0)
+            .get_all_relations::<FriendsWith>()
+            .unwrap()
+            .into_iter()
+            .map(|(target, _)| target)
+            .collect::<Vec<_>>(),
+        [e1, e2, e3]
+    );
+    assert!(world.entity(e2).get_relation::<FriendsWith>(e2).is_some());
+
+    world.entity_mut(e1).clear_relations::<FriendsWith>();
+    assert!(world.entity(e0).get_relation::<FriendsWith>(e1).is_none());
+    world.despawn(e0);
+    for e in [e1, e3] {
+        assert!(world.entity(e).get_all_relations::<FriendsWith>().is_none());
+        assert!(world.entity(e).get_all_noitalers::<FriendsWith>().is_none());
+    }
+    assert_eq!(
+        world
+            .entity(e2)
+            .get_all_relations::<FriendsWith>()
+            .unwrap()
+            .into_iter()
+            .map(|(target, _)| target)
+            .collect::<Vec<_>>(),
+        [e2]
+    );
+    assert_relation_graph_good::<FriendsWith>(&mut world);
+}
+
+#[test]
+fn at_most_restriction() {
+    use crate::restriction::{AtMost, EvictNewest, Reject};
+
+    struct Oldest;
+    impl RelKind for Oldest {
+        type Target = Entity;
+        type SourceRestriction = AtMost<2>;
+        type TargetRestriction = AtMost<2, EvictNewest>;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    #[derive(Debug)]
+    struct Rejecting;
+    impl RelKind for Rejecting {
+        type Target = Entity;
+        type SourceRestriction = AtMost<2, Reject>;
+        type TargetRestriction = AtMost<1, Reject>;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [e0, e1, e2, e3, e4] = [(); 5].map(|_| world.spawn(()).id());
+    let targets = |world: &World, e| {
+        world
+            .entity(e)
+            .get_all_relations::<Oldest>()
+            .into_iter()
+            .flatten()
+            .map(|(target, _)| target)
+            .collect::<Vec<_>>()
+    };
+
+    world.entity_mut(e0).insert_relation(Oldest, e1);
+    world.entity_mut(e0).insert_relation(Oldest, e2);
+    let outcome = world
+        .entity_mut(e0)
+        .try_insert_relation(Oldest, e3)
+        .unwrap();
+    assert_eq!(outcome.evicted_target.map(|(target, _)| target), Some(e1));
+    assert_eq!(targets(&world, e0), [e2, e3]);
+    // replacing an existing edge never evicts
+    let outcome = world
+        .entity_mut(e0)
+        .try_insert_relation(Oldest, e2)
+        .unwrap();
+    assert!(outcome.replaced.is_some() && outcome.evicted_target.is_none());
+
+    world.entity_mut(e1).insert_relation(Oldest, e3);
+    let outcome = world
+        .entity_mut(e2)
+        .try_insert_relation(Oldest, e3)
+        .unwrap();
+    assert_eq!(outcome.evicted_source.map(|(source, _)| source), Some(e1));
+    assert_eq!(
+        world
+            .entity(e3)
+            .get_all_noitalers::<Oldest>()
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>(),
+        [e0, e2]
+    );
+    assert_relation_graph_good::<Oldest>(&mut world);
+
+    world.entity_mut(e0).insert_relation(Rejecting, e1);
+    world.entity_mut(e0).insert_relation(Rejecting, e2);
+    assert_eq!(
+        world
+            .entity_mut(e0)
+            .try_insert_relation(Rejecting, e3)
+            .unwrap_err(),
+        RelationError::SourceFull(e0)
+    );
+    assert_eq!(
+        world
+            .entity_mut(e4)
+            .try_insert_relation(Rejecting, e1)
+            .unwrap_err(),
+        RelationError::TargetFull(e1)
+    );
+    world.entity_mut(e0).insert_relation(Rejecting, e1);
+    world.entity_mut(e0).remove_relation::<Rejecting>(e1);
+    world.entity_mut(e4).insert_relation(Rejecting, e1);
+    world.entity_mut(e0).insert_relation(Rejecting, e3);
+    assert_relation_graph_good::<Rejecting>(&mut world);
+}
+
+#[test]
+fn restriction_conformance() {
+    use crate::{
+        conformance::check_restriction,
+        restriction::{AtMost, EvictNewest, ManyIndexed, Ordered, Reject},
+    };
+
+    macro_rules! check_restrictions {
+        ($($res:ty),*) => {$({
+            #[derive(Debug, PartialEq)]
+            struct R(Entity);
+            impl From<Entity> for R {
+                fn from(e: Entity) -> R {
+                    R(e)
+                }
+            }
+            impl RelKind for R {
+                type Target = Entity;
+                type SourceRestriction = $res;
+                type TargetRestriction = $res;
+                type Cyclicity = Acyclic;
+                type DespawnPolicy = Detach;
+                type TargetDespawnPolicy = Detach;
+                type Symmetry = Directed;
+            }
+            check_restriction::<R>();
+        })*};
+    }
+
+    check_restrictions!(
+        One,
+        Many,
+        Many<Ordered>,
+        ManyIndexed,
+        AtMost<1>,
+        AtMost<3>,
+        AtMost<3, EvictNewest>,
+        AtMost<2, Reject>
+    );
+}
+
+#[test]
+fn many_indexed_fan_out() {
+    use crate::restriction::ManyIndexed;
+
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = ManyIndexed;
+        type TargetRestriction = ManyIndexed;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let hub = world.spawn_empty().id();
+    let mut leaves = (0..500)
+        .map(|_| world.spawn_empty().id())
+        .collect::<Vec<_>>();
+    for &leaf in &leaves {
+        world.entity_mut(leaf).insert_relation(R, hub);
+        world.entity_mut(hub).insert_relation(R, leaf);
+    }
+    assert_relation_graph_good::<R>(&mut world);
+
+    // removing out of order moves edges around inside of the storages
+    for n in 0..250 {
+        let leaf = leaves.remove((n * 7) % leaves.len());
+        if n % 2 == 0 {
+            world.despawn(leaf);
+        } else {
+            world.entity_mut(leaf).remove_relation::<R>(hub);
+            world.entity_mut(hub).remove_relation::<R>(leaf);
+            assert!(world.entity(hub).get_noitaler::<R>(leaf).is_none());
+            assert!(world.entity(hub).get_relation::<R>(leaf).is_none());
+        }
+    }
+    for &leaf in &leaves {
+        assert!(world.entity(hub).get_noitaler::<R>(leaf).is_some());
+        assert!(world.entity(hub).get_relation::<R>(leaf).is_some());
+        assert!(world.entity(leaf).get_relation::<R>(hub).is_some());
+    }
+    assert_eq!(
+        world
+            .entity(hub)
+            .get_all_noitalers::<R>()
+            .unwrap()
+            .into_iter()
+            .count(),
+        250
+    );
+    assert_relation_graph_good::<R>(&mut world);
+
+    world.despawn(hub);
+    assert_relation_graph_good::<R>(&mut world);
+}
+
+#[test]
+fn ordered_relations() {
+    use crate::restriction::{Many, Ordered};
+
+    #[derive(Debug, PartialEq)]
+    struct R(u32);
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many<Ordered>;
+        type TargetRestriction = Many<Ordered>;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    fn targets(world: &World, source: Entity) -> Vec<Entity> {
+        let source = world.entity(source);
+        let rel = source.get_all_relations::<R>().unwrap();
+        rel.into_iter().map(|(target, _)| target).collect()
+    }
+    fn sources(world: &World, target: Entity) -> Vec<Entity> {
+        let target = world.entity(target);
+        let noi = target.get_all_noitalers::<R>().unwrap();
+        noi.into_iter().collect()
+    }
+
+    let mut world = World::new();
+    let hub = world.spawn_empty().id();
+    let [a, b, c, d, e] = [(); 5].map(|_| world.spawn_empty().id());
+    for (n, &other) in [a, b, c, d].iter().enumerate() {
+        world.entity_mut(hub).insert_relation(R(n as u32), other);
+        world.entity_mut(other).insert_relation(R(n as u32), hub);
+    }
+
+    // removing and despawning keeps the remaining edges in insertion order
+    world.entity_mut(hub).remove_relation::<R>(b);
+    world.entity_mut(b).remove_relation::<R>(hub);
+    assert_eq!(targets(&world, hub), [a, c, d]);
+    assert_eq!(sources(&world, hub), [a, c, d]);
+    world.despawn(a);
+    assert_eq!(targets(&world, hub), [c, d]);
+    assert_eq!(sources(&world, hub), [c, d]);
+
+    world
+        .entity_mut(hub)
+        .insert_relation_at(0, R(4), e)
+        .unwrap();
+    world
+        .entity_mut(hub)
+        .insert_noitaler_at(1, e, R(4))
+        .unwrap();
+    assert_eq!(targets(&world, hub), [e, c, d]);
+    assert_eq!(sources(&world, hub), [c, e, d]);
+    // re-inserting an existing edge moves it
+    world
+        .entity_mut(hub)
+        .insert_relation_at(2, R(5), e)
+        .unwrap();
+    assert_eq!(targets(&world, hub), [c, d, e]);
+    assert_eq!(world.entity(hub).get_relation::<R>(e), Some(&R(5)));
+
+    world.entity_mut(hub).move_relation::<R>(d, 0);
+    world.entity_mut(hub).move_noitaler::<R>(d, 0);
+    assert_eq!(targets(&world, hub), [d, c, e]);
+    assert_eq!(sources(&world, hub), [d, c, e]);
+    // moving a missing edge does nothing
+    world.entity_mut(hub).move_relation::<R>(b, 0);
+    world.entity_mut(hub).move_noitaler::<R>(b, 0);
+    assert_eq!(targets(&world, hub), [d, c, e]);
+    assert_eq!(sources(&world, hub), [d, c, e]);
+
+    world
+        .entity_mut(hub)
+        .sort_relations_by::<R, _>(|(_, data1), (_, data2)| data2.0.cmp(&data1.0));
+    assert_eq!(targets(&world, hub), [e, d, c]);
+    let hub_ref = world.entity(hub);
+    let data = hub_ref.get_all_relations::<R>().unwrap();
+    let data = data.into_iter().map(|(_, data)| data.0).collect::<Vec<_>>();
+    assert_eq!(data, [5, 3, 2]);
+    world
+        .entity_mut(hub)
+        .sort_noitalers_by::<R, _>(|source1, source2| source1.cmp(&source2));
+    let mut sorted = sources(&world, hub);
+    sorted.sort();
+    assert_eq!(sources(&world, hub), sorted);
+    assert_relation_graph_good::<R>(&mut world);
+
+    // sorting edges that are already sorted does not mark them as changed
+    world.clear_trackers();
+    world
+        .entity_mut(hub)
+        .sort_relations_by::<R, _>(|(_, data1), (_, data2)| data2.0.cmp(&data1.0))
+        .sort_noitalers_by::<R, _>(|source1, source2| source1.cmp(&source2));
+    let mut changed = world.query_filtered::<(), Or<(ChangedRelation<R>, ChangedNoitaler<R>)>>();
+    assert_eq!(changed.iter(&world).count(), 0);
+}
+
+#[test]
+#[should_panic]
+fn move_relation_out_of_bounds() {
+    use crate::restriction::{Many, Ordered};
+
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many<Ordered>;
+        type TargetRestriction = Many<Ordered>;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [source, target] = [(); 2].map(|_| world.spawn_empty().id());
+    world
+        .entity_mut(source)
+        .insert_relation(R, target)
+        .move_relation::<R>(target, 1);
+}
+
+#[test]
+fn insert_at_out_of_bounds() {
+    use crate::restriction::{Many, Ordered};
+
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many<Ordered>;
+        type TargetRestriction = Many<Ordered>;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [hub, a, b, c] = [(); 4].map(|_| world.spawn_empty().id());
+    world.entity_mut(hub).insert_relation_at(0, R, a).unwrap();
+    // indices past the end are clamped to the last position
+    world.entity_mut(hub).insert_relation_at(5, R, b).unwrap();
+    world.entity_mut(hub).insert_noitaler_at(5, a, R).unwrap();
+    world.entity_mut(hub).insert_noitaler_at(5, b, R).unwrap();
+    let hub_ref = world.entity(hub);
+    let targets = hub_ref.get_all_relations::<R>().unwrap();
+    let targets = targets.into_iter().map(|(target, _)| target);
+    assert_eq!(targets.collect::<Vec<_>>(), [a, b]);
+    let sources = hub_ref.get_all_noitalers::<R>().unwrap();
+    assert_eq!(sources.into_iter().collect::<Vec<_>>(), [a, b]);
+
+    // dead sources and targets leave the world untouched
+    world.despawn(c);
+    let result = world.entity_mut(hub).insert_noitaler_at(0, c, R);
+    assert_eq!(result.map(drop), Err(RelationError::DeadSource(c)));
+    let result = world.entity_mut(hub).insert_relation_at(0, R, c);
+    assert_eq!(result.map(drop), Err(RelationError::DeadTarget(c)));
+    assert_relation_graph_good::<R>(&mut world);
+    let hub_ref = world.entity(hub);
+    assert_eq!(
+        hub_ref
+            .get_all_relations::<R>()
+            .unwrap()
+            .into_iter()
+            .count(),
+        2
+    );
+    assert_eq!(
+        hub_ref
+            .get_all_noitalers::<R>()
+            .unwrap()
+            .into_iter()
+            .count(),
+        2
+    );
+}
+
+#[derive(Debug, PartialEq, Reflect, FromReflect)]
+struct Likes(u32);
+
+impl RelKind for Likes {
+    type Target = Entity;
+    type SourceRestriction = Many;
+    type TargetRestriction = Many;
+    type Cyclicity = Cyclic;
+    type DespawnPolicy = Detach;
+    type TargetDespawnPolicy = Detach;
+    type Symmetry = Directed;
+}
+
+fn reflect_likes(app: &mut bevy::app::App) -> crate::ReflectRelation {
+    use crate::{app::RelationAppExt, ReflectRelation};
+    use bevy::app::AppTypeRegistry;
+
+    app.register_relation::<Likes>();
+    app.world
+        .resource::<AppTypeRegistry>()
+        .read()
+        .get_type_data::<ReflectRelation>(std::any::TypeId::of::<Likes>())
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn reflect_relation() {
+    use crate::relation_kinds;
+    use bevy::app::{App, AppTypeRegistry};
+
+    let mut app = App::new();
+    let reflect = reflect_likes(&mut app);
+    let registry = app.world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let world = &mut app.world;
+    let [a, b, c] = [(); 3].map(|_| world.spawn_empty().id());
+
+    reflect.insert(world, a, &Likes(1), &b).unwrap();
+    assert_eq!(world.entity(a).get_relation::<Likes>(b), Some(&Likes(1)));
+
+    let kinds = relation_kinds(&registry, world, a);
+    assert_eq!(kinds.len(), 1);
+    assert_eq!(kinds[0].type_name(), std::any::type_name::<Likes>());
+    assert_eq!(relation_kinds(&registry, world, b).len(), 1);
+    assert!(relation_kinds(&registry, world, c).is_empty());
+
+    let targets = reflect.targets(world, a);
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0].downcast_ref::<Entity>(), Some(&b));
+    assert_eq!(reflect.sources(world, b), [a]);
+
+    let data = reflect.data(world, a, &b).unwrap();
+    assert_eq!(data.downcast_ref::<Likes>(), Some(&Likes(1)));
+    assert!(reflect.data(world, a, &c).is_none());
+    reflect.data_mut(world, a, &b).unwrap().apply(&Likes(2));
+    assert_eq!(world.entity(a).get_relation::<Likes>(b), Some(&Likes(2)));
+
+    reflect.remove(world, a, &b);
+    assert!(reflect.targets(world, a).is_empty());
+    assert!(relation_kinds(&registry, world, b).is_empty());
+}
+
+#[test]
+fn reflect_insert_dead_source() {
+    let mut app = bevy::app::App::new();
+    let reflect = reflect_likes(&mut app);
+    let world = &mut app.world;
+    let [a, b] = [(); 2].map(|_| world.spawn_empty().id());
+    world.despawn(a);
+
+    let result = reflect.insert(world, a, &Likes(1), &b);
+    assert_eq!(result, Err(RelationError::DeadSource(a)));
+    assert!(reflect.sources(world, b).is_empty());
+}
+
+#[test]
+fn reflect_remove_dead_source() {
+    let mut app = bevy::app::App::new();
+    let reflect = reflect_likes(&mut app);
+    let world = &mut app.world;
+    let [a, b] = [(); 2].map(|_| world.spawn_empty().id());
+    world.despawn(a);
+
+    reflect.remove(world, a, &b);
+    assert!(world.get_entity(a).is_none());
+    assert!(reflect.sources(world, b).is_empty());
+}
+
+#[test]
+fn scene_relations() {
+    use crate::{
+        app::RelationAppExt, dynamic_scene_with_relations, load_scene_relations,
+        scene::SceneRelation,
+    };
+    use bevy::{
+        app::{App, AppTypeRegistry},
+        ecs::entity::EntityMap,
+        reflect::{FromReflect, Reflect},
+        scene::serde::SceneDeserializer,
+    };
+    use serde::de::DeserializeSeed;
+
+    #[derive(Debug, PartialEq, Reflect, FromReflect)]
+    struct R(u32);
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many;
+        type TargetRestriction = Many;
+        type Cyclicity = Acyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    fn make_app() -> App {
+        let mut app = App::new();
+        app.register_type::<u32>().register_scene_relation::<R>();
+        app
+    }
+
+    let mut app = make_app();
+    let registry = app.world.resource::<AppTypeRegistry>().clone();
+    let world = &mut app.world;
+    let [a, b, c] = [(); 3].map(|_| world.spawn_empty().id());
+    world
+        .entity_mut(a)
+        .insert_relation(R(1), b)
+        .insert_relation(R(2), c);
+    world.entity_mut(b).insert_relation(R(3), c);
+
+    let scene = dynamic_scene_with_relations(world, &registry);
+    assert!(world
+        .query::<&SceneRelation<R>>()
+        .iter(world)
+        .next()
+        .is_none());
+    let ron = scene.serialize_ron(&registry).unwrap();
+
+    let mut app = make_app();
+    // shift entity ids so that unmapped targets would be noticed
+    app.world.spawn_empty();
+    let scene = SceneDeserializer {
+        type_registry: &registry.read(),
+    }
+    .deserialize(&mut ron::de::Deserializer::from_str(&ron).unwrap())
+    .unwrap();
+    let mut entity_map = EntityMap::default();
+    scene
+        .write_to_world(&mut app.world, &mut entity_map)
+        .unwrap();
+    app.update();
+
+    let world = &mut app.world;
+    let saved = [a, b, c];
+    let [a, b, c] = saved.map(|e| entity_map.get(e).unwrap());
+    assert_eq!(world.entity(a).get_relation::<R>(b), Some(&R(1)));
+    assert_eq!(world.entity(a).get_relation::<R>(c), Some(&R(2)));
+    assert_eq!(world.entity(b).get_relation::<R>(c), Some(&R(3)));
+    assert!(world.entity(c).get_all_relations::<R>().is_none());
+    assert!(world
+        .query::<&SceneRelation<R>>()
+        .iter(world)
+        .next()
+        .is_none());
+    assert_relation_graph_good::<R>(world);
+
+    // loading still checks cyclicity
+    world.entity_mut(c).insert(SceneRelation {
+        targets: vec![a],
+        data: vec![R(4)],
+    });
+    load_scene_relations(world);
+    assert!(world.entity(c).get_all_relations::<R>().is_none());
+    assert_relation_graph_good::<R>(world);
+
+    // edges to targets outside of the scene are dropped rather than pointing at whichever entity
+    // reuses the old id
+    let mut app = make_app();
+    for _ in 0..3 {
+        app.world.spawn_empty();
+    }
+    let mut scene = SceneDeserializer {
+        type_registry: &registry.read(),
+    }
+    .deserialize(&mut ron::de::Deserializer::from_str(&ron).unwrap())
+    .unwrap();
+    scene
+        .entities
+        .retain(|entity| entity.entity != saved[2].index());
+    let mut entity_map = EntityMap::default();
+    scene
+        .write_to_world(&mut app.world, &mut entity_map)
+        .unwrap();
+    app.update();
+
+    let world = &mut app.world;
+    let [a, b] = [saved[0], saved[1]].map(|e| entity_map.get(e).unwrap());
+    let a = world.entity(a);
+    let targets = a.get_all_relations::<R>().unwrap();
+    assert_eq!(targets.into_iter().collect::<Vec<_>>(), [(b, &R(1))]);
+    assert!(world.entity(b).get_all_relations::<R>().is_none());
+    assert_relation_graph_good::<R>(world);
+}
+
+#[test]
+fn serialized_graph_round_trip() {
+    use crate::{GraphImportError, SerializedGraph};
+    use bevy::ecs::entity::EntityMap;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct R(u32);
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = One;
+        type TargetRestriction = Many;
+        type Cyclicity = Acyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [root, a, b, c] = [(); 4].map(|_| world.spawn_empty().id());
+    world.entity_mut(a).insert_relation(R(1), root);
+    world.entity_mut(b).insert_relation(R(2), root);
+    world.entity_mut(c).insert_relation(R(3), a);
+
+    let graph = SerializedGraph::<R>::export(&mut world);
+    assert_eq!(graph.nodes, [root, a, b, c]);
+    assert_eq!(graph.edges, [(1, 0, R(1)), (2, 0, R(2)), (3, 1, R(3))]);
+    assert_eq!(SerializedGraph::<R>::export(&mut world), graph);
+    let shape = SerializedGraph::<()>::export_with(&mut world, |_: &R| ());
+    assert_eq!(shape.edges, [(1, 0, ()), (2, 0, ()), (3, 1, ())]);
+
+    let ron = ron::to_string(&graph).unwrap();
+    let graph = ron::from_str::<SerializedGraph<R>>(&ron).unwrap();
+
+    let mut world = World::new();
+    // shift entity ids so that unmapped nodes would be noticed
+    world.spawn_empty();
+    let mut entity_map = EntityMap::default();
+    graph.import(&mut world, &mut entity_map).unwrap();
+    let [root, a, b, c] = [root, a, b, c].map(|e| entity_map.get(e).unwrap());
+    assert_eq!(world.entity(a).get_relation::<R>(root), Some(&R(1)));
+    assert_eq!(world.entity(b).get_relation::<R>(root), Some(&R(2)));
+    assert_eq!(world.entity(c).get_relation::<R>(a), Some(&R(3)));
+    assert_relation_graph_good::<R>(&mut world);
+
+    let reexported = SerializedGraph::<R>::export(&mut world);
+    assert_eq!(reexported.nodes, [root, a, b, c]);
+    assert_eq!(reexported.edges, graph.edges);
+
+    // importing goes through the usual checks
+    let cycle = SerializedGraph {
+        nodes: graph.nodes.clone(),
+        edges: vec![(0, 3, R(4))],
+    };
+    assert!(matches!(
+        cycle.import(&mut world, &mut entity_map),
+        Err(GraphImportError::Relation(0, RelationError::Cycle(_)))
+    ));
+    assert_relation_graph_good::<R>(&mut world);
+
+    // failed imports leave the world and `entity_map` as they were
+    let entities = world.entities().len();
+    let new_node = Entity::from_raw(100);
+    let mut failing = SerializedGraph {
+        nodes: [&graph.nodes[..], &[new_node]].concat(),
+        edges: vec![(1, 4, R(5)), (2, 0, R(6)), (7, 0, R(7))],
+    };
+    assert_eq!(
+        failing.import(&mut world, &mut entity_map),
+        Err(GraphImportError::MissingNode(2))
+    );
+    failing.edges[2] = (3, 3, R(7));
+    assert_eq!(
+        failing.import(&mut world, &mut entity_map),
+        Err(GraphImportError::Relation(2, RelationError::SelfEdge(c)))
+    );
+    assert_eq!(world.entities().len(), entities);
+    assert!(entity_map.get(new_node).is_err());
+    assert_eq!(world.entity(a).get_relation::<R>(root), Some(&R(1)));
+    assert_eq!(world.entity(b).get_relation::<R>(root), Some(&R(2)));
+    assert_relation_graph_good::<R>(&mut world);
+}
+
+#[test]
+fn commands_on_despawned_source() {
+    use crate::EntityCommandsExt;
+    use bevy::ecs::system::CommandQueue;
+
+    struct R;
+    impl RelKind for R {
+        type Target = Entity;
+        type SourceRestriction = Many;
+        type TargetRestriction = Many;
+        type Cyclicity = Cyclic;
+        type DespawnPolicy = Detach;
+        type TargetDespawnPolicy = Detach;
+        type Symmetry = Directed;
+    }
+
+    let mut world = World::new();
+    let [source, target] = [(); 2].map(|_| world.spawn(()).id());
+    world.entity_mut(source).insert_relation(R, target);
+
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, &world);
+    commands
+        .entity(source)
+        .try_insert_relation(R, target)
+        .remove_relation::<R>(target)
+        .clear_relations::<R>()
+        .clear_noitalers::<R>();
+    world.despawn(source);
+    queue.apply(&mut world);
+
+    assert!(world.entity(target).get_all_noitalers::<R>().is_none());
+    assert_relation_graph_good::<R>(&mut world);
+}
+
+#[test]
+fn relation_kind_macro() {
+    use crate::{relation_kind, RelationTarget};
+
+    struct Defaults;
+    relation_kind! {
+        impl RelKind for Defaults {
+            type SourceRestriction = One;
+            type TargetRestriction = Many;
+            type Cyclicity = Acyclic;
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct Key(u8);
+    impl RelationTarget for Key {}
+
+    struct Overridden;
+    relation_kind! {
+        impl RelKind for Overridden {
+            type Symmetry = Directed;
+            type Cyclicity = Cyclic;
+            type Target = Key;
+            type TargetRestriction = Many;
+            type SourceRestriction = Many;
+            type DespawnPolicy = Detach;
+            type TargetDespawnPolicy = Detach;
+        }
+    }
+
+    fn assert_defaults<
+        T: RelKind<
+            Target = Entity,
+            DespawnPolicy = Recursive,
+            TargetDespawnPolicy = Detach,
+            Symmetry = Directed,
+        >,
+    >() {
+    }
+    assert_defaults::<Defaults>();
+
+    let mut world = World::new();
+    let [e0, e1, e2] = [(); 3].map(|_| world.spawn(()).id());
+    world.entity_mut(e1).insert_relation(Defaults, e0);
+    world.entity_mut(e2).insert_relation(Defaults, e1);
+    world.entity_mut(e1).insert_relation(Overridden, Key(0));
+    // despawning a source despawns its targets, sources of a despawned target are detached
+    world.despawn(e1);
+    assert!(world.get_entity(e0).is_none());
+    assert!(world.entity(e2).get_all_relations::<Defaults>().is_none());
+    assert_relation_graph_good::<Defaults>(&mut world);
+}
@@ -0,0 +1,62 @@
+use bevy::{
+    ecs::{prelude::*, system::SystemParam},
+    utils::HashMap,
+};
+
+use crate::{NoitalerRef, RelKind, RelationRef};
+
+/// Visits entities with edges of kind `T` so that every target comes before its sources, i.e.
+/// parents before children. Entities that are part of a cycle and anything that (indirectly)
+/// relates to them are never visited so this should be used with [`Acyclic`] kinds.
+///
+/// Entities in the same level from [`TopoOrder::levels`] do not relate to each other so each level
+/// can be processed in parallel, e.g. by splitting it between tasks on the `ComputeTaskPool`.
+///
+/// [`Acyclic`]: crate::cyclicity::Acyclic
+#[derive(SystemParam)]
+pub struct TopoOrder<'w, 's, T: RelKind<Target = Entity>> {
+    roots: Query<'w, 's, (Entity, Option<RelationRef<T>>, NoitalerRef<T>)>,
+    relations: Query<'w, 's, RelationRef<T>>,
+    noitalers: Query<'w, 's, NoitalerRef<T>>,
+}
+impl<'w, 's, T: RelKind<Target = Entity>> TopoOrder<'w, 's, T> {
+    /// Returns every entity in topological order
+    pub fn iter(&self) -> impl Iterator<Item = Entity> {
+        self.levels().into_iter().flatten()
+    }
+
+    /// Returns entities grouped by depth, the first level contains every entity that is a target
+    /// but not a source, the level of any other entity is one after the deepest of its targets.
+    pub fn levels(&self) -> Vec<Vec<Entity>> {
+        // number of targets of each source that have not been visited yet
+        let mut remaining = HashMap::<Entity, usize>::default();
+        let mut levels = Vec::new();
+        // storages emptied by `RelationsMut` are still around until commands are applied
+        let mut current = self
+            .roots
+            .iter()
+            .filter_map(|(entity, relations, sources)| {
+                let is_root = relations.is_none_or(|relations| relations.iter().next().is_none())
+                    && sources.into_iter().next().is_some();
+                is_root.then_some(entity)
+            })
+            .collect::<Vec<_>>();
+        while !current.is_empty() {
+            let mut next = Vec::new();
+            for &entity in &current {
+                for source in self.noitalers.get(entity).ok().into_iter().flatten() {
+                    let remaining = remaining
+                        .entry(source)
+                        .or_insert_with(|| self.relations.get(source).unwrap().iter().count());
+                    *remaining -= 1;
+                    if *remaining == 0 {
+                        next.push(source);
+                    }
+                }
+            }
+            levels.push(current);
+            current = next;
+        }
+        levels
+    }
+}
@@ -0,0 +1,290 @@
+use std::{collections::VecDeque, marker::PhantomData};
+
+use bevy::{
+    ecs::{
+        prelude::*,
+        world::{EntityMut, EntityRef},
+    },
+    utils::HashSet,
+};
+
+use crate::{EntityRefExt, RelKind, RelationTarget, Relations, RelationsMut};
+
+/// Read access to the graph formed by relations of kind `T`. Implemented for `&World`,
+/// [`Relations`] and [`RelationsMut`] so that traversals work both inside and outside of systems.
+/// Targets that are not entities are skipped.
+pub trait RelationGraph<T: RelKind> {
+    fn for_each_target(&self, entity: Entity, f: impl FnMut(Entity));
+    fn for_each_source(&self, entity: Entity, f: impl FnMut(Entity));
+
+    fn first_target(&self, entity: Entity) -> Option<Entity> {
+        let mut first = None;
+        self.for_each_target(entity, |target| {
+            first.get_or_insert(target);
+        });
+        first
+    }
+
+    fn first_source(&self, entity: Entity) -> Option<Entity> {
+        let mut first = None;
+        self.for_each_source(entity, |source| {
+            first.get_or_insert(source);
+        });
+        first
+    }
+
+    fn has_sources(&self, entity: Entity) -> bool {
+        let mut has_sources = false;
+        self.for_each_source(entity, |_| has_sources = true);
+        has_sources
+    }
+}
+
+impl<T: RelKind> RelationGraph<T> for &World {
+    fn for_each_target(&self, entity: Entity, f: impl FnMut(Entity)) {
+        if let Some(entity) = self.get_entity(entity) {
+            if let Some(relations) = entity.get_all_relations::<T>() {
+                relations
+                    .iter()
+                    .filter_map(|(target, _)| target.as_entity())
+                    .for_each(f);
+            }
+        }
+    }
+
+    fn for_each_source(&self, entity: Entity, f: impl FnMut(Entity)) {
+        if let Some(entity) = self.get_entity(entity) {
+            if let Some(noitalers) = entity.get_all_noitalers::<T>() {
+                noitalers.iter().for_each(f);
+            }
+        }
+    }
+}
+
+impl<T: RelKind> RelationGraph<T> for &RelationsMut<'_, '_, T> {
+    fn for_each_target(&self, entity: Entity, f: impl FnMut(Entity)) {
+        self.targets(entity)
+            .filter_map(|target| target.as_entity())
+            .for_each(f);
+    }
+
+    fn for_each_source(&self, entity: Entity, f: impl FnMut(Entity)) {
+        self.sources(entity).for_each(f);
+    }
+}
+
+impl<T: RelKind> RelationGraph<T> for &Relations<'_, '_, T> {
+    fn for_each_target(&self, entity: Entity, f: impl FnMut(Entity)) {
+        self.targets(entity)
+            .filter_map(|target| target.as_entity())
+            .for_each(f);
+    }
+
+    fn for_each_source(&self, entity: Entity, f: impl FnMut(Entity)) {
+        self.sources(entity).for_each(f);
+    }
+}
+
+/// Traversals over tree shaped relation graphs, i.e. sources have [`restriction::One`] target.
+/// Ancestors are found by following the targets of an entity and descendants by following its
+/// sources. If a source has multiple targets only the first one is followed. Entities are never
+/// visited twice so traversals terminate on [`cyclicity::Cyclic`] graphs.
+///
+/// [`restriction::One`]: crate::restriction::One
+/// [`cyclicity::Cyclic`]: crate::cyclicity::Cyclic
+pub trait Traverse {
+    /// Iterates the target of this entity, then its target and so on. Does not include this entity.
+    fn ancestors<T: RelKind<Target = Entity>>(&self) -> Ancestors<T, &World>;
+    /// Iterates every entity that (indirectly) relates to this one in breadth first order. Does
+    /// not include this entity.
+    fn descendants_bfs<T: RelKind<Target = Entity>>(&self) -> Descendants<T, &World>;
+    /// Iterates every entity that (indirectly) relates to this one in depth first pre-order. Does
+    /// not include this entity.
+    fn descendants_dfs<T: RelKind<Target = Entity>>(&self) -> Descendants<T, &World>;
+    /// The last ancestor of this entity or this entity if it has no target
+    fn root<T: RelKind<Target = Entity>>(&self) -> Entity;
+    /// Iterates the descendants of this entity that have no sources
+    fn leaves<T: RelKind<Target = Entity>>(&self) -> Leaves<T, &World>;
+    /// Iterates the other sources of this entity's target
+    fn siblings<T: RelKind<Target = Entity>>(&self) -> Siblings;
+}
+
+macro_rules! impl_traverse {
+    ($($t:ty),*) => {$(
+        impl Traverse for $t {
+            fn ancestors<T: RelKind<Target = Entity>>(&self) -> Ancestors<T, &World> {
+                ancestors(self.world(), self.id())
+            }
+            fn descendants_bfs<T: RelKind<Target = Entity>>(&self) -> Descendants<T, &World> {
+                descendants_bfs(self.world(), self.id())
+            }
+            fn descendants_dfs<T: RelKind<Target = Entity>>(&self) -> Descendants<T, &World> {
+                descendants_dfs(self.world(), self.id())
+            }
+            fn root<T: RelKind<Target = Entity>>(&self) -> Entity {
+                root::<T, _>(self.world(), self.id())
+            }
+            fn leaves<T: RelKind<Target = Entity>>(&self) -> Leaves<T, &World> {
+                leaves(self.world(), self.id())
+            }
+            fn siblings<T: RelKind<Target = Entity>>(&self) -> Siblings {
+                siblings::<T, _>(self.world(), self.id())
+            }
+        }
+    )*};
+}
+impl_traverse!(EntityRef<'_>, EntityMut<'_>);
+
+pub(crate) fn ancestors<T: RelKind<Target = Entity>, G: RelationGraph<T>>(
+    graph: G,
+    entity: Entity,
+) -> Ancestors<T, G> {
+    let mut visited = HashSet::default();
+    visited.insert(entity);
+    Ancestors {
+        next: graph.first_target(entity),
+        graph,
+        visited,
+        _p: PhantomData,
+    }
+}
+
+pub(crate) fn descendants_bfs<T: RelKind<Target = Entity>, G: RelationGraph<T>>(
+    graph: G,
+    entity: Entity,
+) -> Descendants<T, G> {
+    descendants(graph, entity, false)
+}
+
+pub(crate) fn descendants_dfs<T: RelKind<Target = Entity>, G: RelationGraph<T>>(
+    graph: G,
+    entity: Entity,
+) -> Descendants<T, G> {
+    descendants(graph, entity, true)
+}
+
+fn descendants<T: RelKind<Target = Entity>, G: RelationGraph<T>>(
+    graph: G,
+    entity: Entity,
+    depth_first: bool,
+) -> Descendants<T, G> {
+    let mut visited = HashSet::default();
+    visited.insert(entity);
+    let mut descendants = Descendants {
+        graph,
+        queue: VecDeque::new(),
+        visited,
+        depth_first,
+        _p: PhantomData,
+    };
+    descendants.push_sources(entity);
+    descendants
+}
+
+pub(crate) fn root<T: RelKind<Target = Entity>, G: RelationGraph<T>>(
+    graph: G,
+    entity: Entity,
+) -> Entity {
+    ancestors(graph, entity).last().unwrap_or(entity)
+}
+
+pub(crate) fn leaves<T: RelKind<Target = Entity>, G: RelationGraph<T>>(
+    graph: G,
+    entity: Entity,
+) -> Leaves<T, G> {
+    Leaves(descendants(graph, entity, true))
+}
+
+pub(crate) fn siblings<T: RelKind<Target = Entity>, G: RelationGraph<T>>(
+    graph: G,
+    entity: Entity,
+) -> Siblings {
+    let mut siblings = Vec::new();
+    if let Some(target) = graph.first_target(entity) {
+        graph.for_each_source(target, |source| {
+            if source != entity {
+                siblings.push(source);
+            }
+        });
+    }
+    Siblings(siblings.into_iter())
+}
+
+pub struct Ancestors<T: RelKind<Target = Entity>, G: RelationGraph<T>> {
+    graph: G,
+    next: Option<Entity>,
+    visited: HashSet<Entity>,
+    _p: PhantomData<fn() -> T>,
+}
+impl<T: RelKind<Target = Entity>, G: RelationGraph<T>> Iterator for Ancestors<T, G> {
+    type Item = Entity;
+    fn next(&mut self) -> Option<Entity> {
+        let current = self.next.take()?;
+        if !self.visited.insert(current) {
+            return None;
+        }
+        self.next = self.graph.first_target(current);
+        Some(current)
+    }
+}
+
+pub struct Descendants<T: RelKind<Target = Entity>, G: RelationGraph<T>> {
+    graph: G,
+    queue: VecDeque<Entity>,
+    visited: HashSet<Entity>,
+    depth_first: bool,
+    _p: PhantomData<fn() -> T>,
+}
+impl<T: RelKind<Target = Entity>, G: RelationGraph<T>> Descendants<T, G> {
+    fn push_sources(&mut self, entity: Entity) {
+        let mut sources = Vec::new();
+        let visited = &self.visited;
+        self.graph.for_each_source(entity, |source| {
+            if !visited.contains(&source) {
+                sources.push(source);
+            }
+        });
+        match self.depth_first {
+            // reversed so that the first source is visited first
+            true => self.queue.extend(sources.into_iter().rev()),
+            false => self.queue.extend(sources),
+        }
+    }
+}
+impl<T: RelKind<Target = Entity>, G: RelationGraph<T>> Iterator for Descendants<T, G> {
+    type Item = Entity;
+    fn next(&mut self) -> Option<Entity> {
+        loop {
+            let current = match self.depth_first {
+                true => self.queue.pop_back()?,
+                false => self.queue.pop_front()?,
+            };
+            // an entity can be queued multiple times before it is visited
+            if self.visited.insert(current) {
+                self.push_sources(current);
+                return Some(current);
+            }
+        }
+    }
+}
+
+pub struct Leaves<T: RelKind<Target = Entity>, G: RelationGraph<T>>(Descendants<T, G>);
+impl<T: RelKind<Target = Entity>, G: RelationGraph<T>> Iterator for Leaves<T, G> {
+    type Item = Entity;
+    fn next(&mut self) -> Option<Entity> {
+        loop {
+            let current = self.0.next()?;
+            if !self.0.graph.has_sources(current) {
+                return Some(current);
+            }
+        }
+    }
+}
+
+pub struct Siblings(std::vec::IntoIter<Entity>);
+impl Iterator for Siblings {
+    type Item = Entity;
+    fn next(&mut self) -> Option<Entity> {
+        self.0.next()
+    }
+}